@@ -0,0 +1,14 @@
+//! Print a report of the conformance of the parser to the specification.
+//!
+//! Pass `--json` to print the report as JSON rather than Markdown.
+
+use std::env::args;
+
+fn main() {
+    let report = rst::conformance::run();
+    if args().any(|arg| arg == "--json") {
+        println!("{}", report.to_json());
+    } else {
+        print!("{}", report.to_markdown());
+    }
+}
@@ -1,4 +1,4 @@
-//! A simple example that prcoesses stdin.
+//! A simple example that processes stdin.
 
 use std::error::Error;
 use std::io::stdin;
@@ -19,7 +19,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut source = ReaderSource::from_reader("stdin", stdin());
     for token in TokenStream::try_new(&mut source)? {
         let (token, span) = token?;
-        println!("{}: {:?}", span, token);
+        println!("{}: {:?} = {:?}", span, span.excerpt().unwrap(), token);
     }
 
     Ok(())
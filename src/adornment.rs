@@ -0,0 +1,196 @@
+//! Characters used to adorn section titles and transitions.
+//!
+//! The tokenizer, the parser and anything that emits reStructuredText all consult the tables in
+//! this module so that they agree on which characters form an adornment.
+
+use std::collections::HashMap;
+
+/// Characters that may be used as adornments.
+///
+/// The following are all valid adornment characters:
+///
+/// ```text
+/// ! " # $ % & ' ( ) * + , - . / : ; < = > ? @ [ \ ] ^ _ ` { | } ~
+/// ```
+///
+/// Some characters are more suitable than others; see
+/// [`RECOMMENDED_CHARS`](constant.RECOMMENDED_CHARS.html).
+pub const ADORNMENT_CHARS: &[char] = &[
+    '!', '"', '#', '$', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/', ':', ';', '<', '=',
+    '>', '?', '@', '[', '\\', ']', '^', '_', '`', '{', '|', '}', '~',
+];
+
+/// Adornment characters recommended by the specification, in the order they are listed there.
+///
+/// ```text
+/// = - ` : . ' " ~ ^ _ * + #
+/// ```
+pub const RECOMMENDED_CHARS: &[char] = &[
+    '=', '-', '`', ':', '.', '\'', '"', '~', '^', '_', '*', '+', '#',
+];
+
+/// The number of overlined styles used before falling back to underlines alone.
+const OVERLINED_TITLES: usize = 2;
+
+/// The character is one of the [adornment characters](constant.ADORNMENT_CHARS.html).
+pub fn is_adornment_char(c: char) -> bool {
+    ADORNMENT_CHARS.contains(&c)
+}
+
+/// The character is one of the [recommended adornment
+/// characters](constant.RECOMMENDED_CHARS.html).
+pub fn is_recommended(c: char) -> bool {
+    RECOMMENDED_CHARS.contains(&c)
+}
+
+/// The adornment style to use for a section title at the given depth.
+///
+/// Returns the adornment character and whether the title also has an overline. The document title
+/// and subtitle (depths 0 and 1) are overlined with `=` and `-`; deeper sections are underlined
+/// with the recommended characters in order. Once those are exhausted the remaining recommended
+/// characters are overlined, followed by the other adornment characters underlined and then
+/// overlined, so every depth is given a distinct style.
+///
+/// # Panics
+///
+/// Panics if `depth` is not less than twice the number of adornment characters, as no distinct
+/// style remains.
+pub fn default_for_depth(depth: usize) -> (char, bool) {
+    let recommended = RECOMMENDED_CHARS.len();
+    let mut index = depth;
+
+    if index < OVERLINED_TITLES {
+        return (RECOMMENDED_CHARS[index], true);
+    }
+    index -= OVERLINED_TITLES;
+
+    if index < recommended {
+        return (RECOMMENDED_CHARS[index], false);
+    }
+    index -= recommended;
+
+    if index < recommended - OVERLINED_TITLES {
+        return (RECOMMENDED_CHARS[OVERLINED_TITLES + index], true);
+    }
+    index -= recommended - OVERLINED_TITLES;
+
+    let mut others = ADORNMENT_CHARS.iter().filter(|&&c| !is_recommended(c));
+    let count = ADORNMENT_CHARS.len() - recommended;
+    match others.nth(index % count) {
+        Some(&c) if index < 2 * count => (c, index >= count),
+        _ => panic!("no distinct adornment style remains for depth {}", depth),
+    }
+}
+
+/// The adornment of a section title.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AdornmentStyle {
+    /// The character repeated in the underline, and in the overline if there is one.
+    pub character: char,
+    /// The title has an overline as well as an underline.
+    pub overline: bool,
+}
+
+/// The heading levels of the adornment styles used in a document.
+///
+/// There is no fixed mapping from styles to levels; each new style encountered is assigned the
+/// level below the deepest seen so far. Levels begin at 1 for the outermost sections.
+#[derive(Debug, Clone, Default)]
+pub struct AdornmentStyleMap {
+    levels: HashMap<AdornmentStyle, u32>,
+}
+
+impl AdornmentStyleMap {
+    /// The level of a style, assigning it the next level if it has not been seen before.
+    pub fn level_of(&mut self, style: AdornmentStyle) -> u32 {
+        let next = self.levels.len() as u32 + 1;
+        *self.levels.entry(style).or_insert(next)
+    }
+
+    /// The level of a style, if it has been seen.
+    pub fn get(&self, style: AdornmentStyle) -> Option<u32> {
+        self.levels.get(&style).copied()
+    }
+
+    /// The number of distinct styles seen.
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::TextSource;
+    use crate::{TokenKind, TokenStream};
+    use std::collections::HashSet;
+
+    #[test]
+    fn recommended_are_adornments() {
+        for &c in RECOMMENDED_CHARS {
+            assert!(is_adornment_char(c), "{:?}", c);
+        }
+        assert!(!is_adornment_char('a'));
+        assert!(!is_adornment_char('•'));
+        assert!(!is_recommended('!'));
+    }
+
+    #[test]
+    fn tokenizer_agrees() {
+        for c in (0..0x80u8).map(char::from).filter(char::is_ascii_graphic) {
+            let text = c.to_string();
+            let mut source = TextSource::from_str("adornment", &text);
+            let (token, _) = TokenStream::try_new(&mut source)
+                .unwrap()
+                .next()
+                .unwrap()
+                .unwrap();
+            assert_eq!(token.is_adornment(), is_adornment_char(c), "{:?}", c);
+        }
+        assert!(!TokenKind::HyphenBullet.is_adornment());
+    }
+
+    #[test]
+    fn depths() {
+        assert_eq!(default_for_depth(0), ('=', true));
+        assert_eq!(default_for_depth(1), ('-', true));
+        assert_eq!(default_for_depth(2), ('=', false));
+        assert_eq!(default_for_depth(3), ('-', false));
+        assert_eq!(default_for_depth(4), ('`', false));
+
+        let depths = 2 * ADORNMENT_CHARS.len();
+        let styles: HashSet<_> = (0..depths).map(default_for_depth).collect();
+        assert_eq!(styles.len(), depths);
+        for (c, _) in styles {
+            assert!(is_adornment_char(c));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_deep() {
+        default_for_depth(2 * ADORNMENT_CHARS.len());
+    }
+
+    #[test]
+    fn style_levels() {
+        let style = |character, overline| AdornmentStyle {
+            character,
+            overline,
+        };
+
+        let mut styles = AdornmentStyleMap::default();
+        assert!(styles.is_empty());
+        assert_eq!(styles.level_of(style('=', true)), 1);
+        assert_eq!(styles.level_of(style('=', false)), 2);
+        assert_eq!(styles.level_of(style('-', false)), 3);
+        assert_eq!(styles.level_of(style('=', false)), 2);
+        assert_eq!(styles.get(style('=', true)), Some(1));
+        assert_eq!(styles.get(style('~', false)), None);
+        assert_eq!(styles.len(), 3);
+    }
+}
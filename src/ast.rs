@@ -1,31 +1,81 @@
 //! Abstract syntax tree.
 
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use url::Url;
 
+use crate::location::{ReaderSource, StringSource};
+use crate::names::{self, SlugOptions};
+use crate::{ParseError, Parser, TokenStream};
+
+mod table;
+mod tree;
+#[cfg(feature = "serde")]
+mod url_serde;
+
+/// The name of a source parsed from a string.
+const STRING_SOURCE: &str = "<string>";
+
 /// A [document][].
 ///
 /// This represents an entire reStructuredText document and forms the root of the tree.
 ///
 /// [document]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#document
-pub struct Document(Body);
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Document(pub(crate) Vec<SectionChildren>);
 
-/// Characters that may be used as adornments.
-///
-/// The following are all valid adornment characters:
+impl Document {
+    /// Parse a document read from `reader`.
+    ///
+    /// The name identifies the document in errors.
+    pub fn from_reader(name: &str, reader: impl Read) -> Result<Document, ParseError> {
+        let mut source = ReaderSource::from_reader(name, reader);
+        Parser::new(TokenStream::try_new(&mut source)?).parse()
+    }
+
+    /// Render the document as a compact, indented tree with one node per line.
+    ///
+    /// Text is summarised with a short excerpt and a count of its words or lines. The format is
+    /// stable and intended for inspecting the result of a parse and for use in tests.
+    pub fn tree_string(&self) -> String {
+        tree::document(self)
+    }
+}
+
+/// Parses a document, named `<string>` in errors.
 ///
-/// ```text
-/// ! " # $ % & ' ( ) * + , - . / : ; < = > ? @ [ \ ] ^ _ ` { | } ~
 /// ```
+/// use rst::ast::Document;
 ///
-/// Some characters are more suitable than others. The following are recommended:
-///
-/// ```text
-/// = - ` : . ' " ~ ^ _ * + #
+/// let document: Document = "A *short* document.".parse()?;
+/// # Ok::<(), rst::ParseError>(())
 /// ```
-pub const ADORNMENT_CHARS: &[char] = &[
-    '!', '"', '#', '$', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/', ':', ';', '<', '=',
-    '>', '?', '@', '[', '\\', ']', '^', '_', '`', '{', '|', '}', '~',
-];
+impl FromStr for Document {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Document, ParseError> {
+        let mut source = StringSource::from_string(STRING_SOURCE.to_owned(), text.to_owned());
+        Parser::new(TokenStream::try_new(&mut source)?).parse()
+    }
+}
+
+impl TryFrom<&str> for Document {
+    type Error = ParseError;
+
+    fn try_from(text: &str) -> Result<Document, ParseError> {
+        text.parse()
+    }
+}
+
+pub use self::table::{TableBuilder, TableError};
+pub use crate::adornment::ADORNMENT_CHARS;
 
 /// A [section][].
 ///
@@ -76,19 +126,73 @@ pub const ADORNMENT_CHARS: &[char] = &[
 ///
 /// [section]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#sections
 /// [transitions]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#transitions
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Section {
-    title: String,
-    children: Vec<SectionChildren>,
+    pub(crate) title: String,
+    pub(crate) children: Vec<SectionChildren>,
 }
 
-/// Children of a section.
-enum SectionChildren {
+/// Children of a section or of the document.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) enum SectionChildren {
     Body(BodyBlock),
     Transition,
     Section(Section),
 }
 
 /// A block that can be embedded within the body of another element.
+///
+/// # Stability
+///
+/// New kinds of block are added as more of the specification is supported, so this enum is
+/// `non_exhaustive` and adding a variant is not a breaking change. Code outside of this crate
+/// that renders or inspects blocks must handle unknown blocks with a wildcard arm. Renderers
+/// should skip unknown blocks (noting the omission in their output where the format allows it)
+/// rather than failing.
+///
+/// ```
+/// use rst::ast::BodyBlock;
+///
+/// fn describe(block: &BodyBlock) -> &'static str {
+///     match block {
+///         BodyBlock::Paragraph(_) => "paragraph",
+///         BodyBlock::List(_) => "list",
+///         _ => "unsupported",
+///     }
+/// }
+/// ```
+///
+/// Matching every variant is not sufficient:
+///
+/// ```compile_fail
+/// use rst::ast::BodyBlock;
+///
+/// fn describe(block: &BodyBlock) -> &'static str {
+///     match block {
+///         BodyBlock::Paragraph(_) => "paragraph",
+///         BodyBlock::List(_) => "list",
+///         BodyBlock::DefinitionList(_) => "definition list",
+///         BodyBlock::FieldList(_) => "field list",
+///         BodyBlock::OptionList(_) => "option list",
+///         BodyBlock::LiteralBlock(_) => "literal block",
+///         BodyBlock::LineBlock(_) => "line block",
+///         BodyBlock::BlockQuote(_) => "block quote",
+///         BodyBlock::DocTest(_) => "doctest",
+///         BodyBlock::Table(_) => "table",
+///         BodyBlock::Footnote(_) => "footnote",
+///         BodyBlock::Citation(_) => "citation",
+///         BodyBlock::Target(_) => "target",
+///         BodyBlock::Directive(_) => "directive",
+///         BodyBlock::Substitution(_) => "substitution",
+///         BodyBlock::Comment(_) => "comment",
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
 pub enum BodyBlock {
     Paragraph(Paragraph),
     List(List),
@@ -109,7 +213,9 @@ pub enum BodyBlock {
 }
 
 /// A sequence of [`BodyBlock`](enum.BodyBlock.html)s.
-pub struct Body(Vec<BodyBlock>);
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Body(pub(crate) Vec<BodyBlock>);
 
 /// A [paragraph][].
 ///
@@ -118,7 +224,9 @@ pub struct Body(Vec<BodyBlock>);
 /// Paragraphs may contain [inline markup](struct.Text.html).
 ///
 /// [paragraph]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#paragraphs
-pub struct Paragraph(Text);
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Paragraph(pub(crate) Text);
 
 /// A list; [bulleted][] or [enumerated][];
 ///
@@ -162,15 +270,29 @@ pub struct Paragraph(Text);
 ///
 /// [bulleted]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#bullet-lists
 /// [enumerated]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#enumerated-lists
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct List {
-    marker: ListMarker,
-    elements: Vec<Body>,
+    pub(crate) marker: ListMarker,
+    pub(crate) elements: Vec<Body>,
+}
+
+impl List {
+    /// The marker identifying the elements of the list.
+    pub fn marker(&self) -> &ListMarker {
+        &self.marker
+    }
 }
 
 /// The kind of marker used to identify elements of the list.
 ///
 /// For enumerated lists, the starting index is also provided.
-enum ListMarker {
+///
+/// More styles of enumeration may be added, so this enum is `non_exhaustive`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum ListMarker {
     /// A standard bulleted list.
     Bullet,
     /// A list enumerated with arabic decimals.
@@ -183,6 +305,49 @@ enum ListMarker {
     RomanUppercase(u64),
     /// A list enumerate with lowercase roman numerals.
     RomanLowercase(u64),
+    /// A list enumerated with `#`, numbered from one by position.
+    AutoNumbered,
+    /// A list marked with the symbols of auto-symbol footnotes, by position.
+    AutoSymbol,
+}
+
+/// The symbols of auto-symbol footnotes, in order.
+const AUTO_SYMBOLS: [char; 10] = ['*', '†', '‡', '§', '¶', '#', '♠', '♥', '♦', '♣'];
+
+impl ListMarker {
+    /// The number of the item at `index`, counting from zero, in an enumerated list.
+    ///
+    /// Bulleted and auto-symbol lists are not numbered.
+    pub fn resolved_number(&self, index: usize) -> Option<u64> {
+        let start = match *self {
+            ListMarker::Bullet | ListMarker::AutoSymbol => return None,
+            ListMarker::AutoNumbered => 1,
+            ListMarker::Arabic(start)
+            | ListMarker::LatinUppercase(start)
+            | ListMarker::LatinLowercase(start)
+            | ListMarker::RomanUppercase(start)
+            | ListMarker::RomanLowercase(start) => start,
+        };
+        start.checked_add(index as u64)
+    }
+
+    /// The symbol of the item at `index`, counting from zero, in an auto-symbol list.
+    pub fn resolved_symbol(&self, index: usize) -> Option<String> {
+        match self {
+            ListMarker::AutoSymbol => Some(auto_symbol(index)),
+            _ => None,
+        }
+    }
+}
+
+/// The auto-symbol at `index`, counting from zero.
+///
+/// Once the symbols are exhausted they are repeated: the eleventh is `**`, the twelfth `††`, and
+/// so on.
+fn auto_symbol(index: usize) -> String {
+    let symbol = AUTO_SYMBOLS[index % AUTO_SYMBOLS.len()];
+    let count = index / AUTO_SYMBOLS.len() + 1;
+    std::iter::repeat(symbol).take(count).collect()
 }
 
 /// A [definition list][].
@@ -209,13 +374,17 @@ enum ListMarker {
 /// ```
 ///
 /// [definition list]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#definition-lists.
-pub struct DefinitionList(Vec<Definition>);
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DefinitionList(pub(crate) Vec<Definition>);
 
 /// A single definition within a [`DefinitionList`](struct.DefinitionList.html).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Definition {
-    term: Text,
-    classifiers: Vec<Text>,
-    definition: Body,
+    pub(crate) term: Text,
+    pub(crate) classifiers: Vec<Text>,
+    pub(crate) definition: Body,
 }
 
 /// A [field list][].
@@ -239,12 +408,31 @@ pub struct Definition {
 ///
 /// [field list]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#field-lists
 /// [rfc822]: http://www.rfc-editor.org/rfc/rfc822.txt
-pub struct FieldList(Vec<Field>);
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FieldList(pub(crate) Vec<Field>);
 
 /// An element of a [`FieldList`](struct.FieldList.html).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Field {
-    marker: Text,
-    body: Body,
+    pub(crate) marker: Text,
+    pub(crate) body: Body,
+}
+
+impl Field {
+    /// The [normalised](../names/fn.normalize_name.html) name of the field.
+    pub fn name(&self) -> String {
+        names::normalize_name(&self.marker.plain_text())
+    }
+
+    /// The body of the field as text, if it consists of a single paragraph.
+    pub fn text(&self) -> Option<&Text> {
+        match &self.body.0[..] {
+            [BodyBlock::Paragraph(Paragraph(text))] => Some(text),
+            _ => None,
+        }
+    }
 }
 
 /// An [option list][].
@@ -289,12 +477,16 @@ pub struct Field {
 ///  * DOS/VMS options consist of a slash and an option letter or word.
 ///
 /// [option list]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#option-lists
-pub struct OptionList(Vec<OptionItem>);
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OptionList(pub(crate) Vec<OptionItem>);
 
 /// An item within an [`OptionList`](struct.OptionList.html).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct OptionItem {
-    options: Vec<(String, Option<String>)>,
-    description: Text,
+    pub(crate) options: Vec<(String, Option<String>)>,
+    pub(crate) description: Text,
 }
 
 /// A [literal block][].
@@ -355,7 +547,9 @@ pub struct OptionItem {
 /// ```
 ///
 /// [literal block]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#literal-blocks
-pub struct LiteralBlock(String);
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LiteralBlock(pub(crate) String);
 
 /// A [line block][].
 ///
@@ -387,12 +581,16 @@ pub struct LiteralBlock(String);
 /// ```
 ///
 /// [line block]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#line-blocks
-pub struct LineBlock(Vec<Line>);
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LineBlock(pub(crate) Vec<Line>);
 
 /// A line within a [`LineBlock`](struct.LineBlock.html).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Line {
-    content: Text,
-    children: Vec<Line>,
+    pub(crate) content: Text,
+    pub(crate) children: Vec<Line>,
 }
 
 /// A [block quote][].
@@ -409,9 +607,11 @@ pub struct Line {
 /// ```
 ///
 /// [block quote]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#block-quotes
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BlockQuote {
-    quote: Body,
-    attribution: Option<Text>,
+    pub(crate) quote: Body,
+    pub(crate) attribution: Option<Text>,
 }
 
 /// A [doctest block][].
@@ -435,7 +635,9 @@ pub struct BlockQuote {
 ///
 /// [doctest block]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#doctest-blocks
 /// [doctest module]: http://www.python.org/doc/current/lib/module-doctest.html
-pub struct DocTest(String);
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DocTest(pub(crate) String);
 
 /// A [table][].
 ///
@@ -475,19 +677,25 @@ pub struct DocTest(String);
 /// ```
 ///
 /// [table]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#tables
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Table {
-    header: Vec<Row>,
-    body: Vec<Row>,
+    pub(crate) header: Vec<Row>,
+    pub(crate) body: Vec<Row>,
 }
 
 /// Rows within a [`Table`](struct.Table.html).
-pub struct Row(Vec<Cell>);
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Row(pub(crate) Vec<Cell>);
 
 /// A cell within a [`Table`](struct.Table.html).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Cell {
-    column_span: u64,
-    row_span: u64,
-    content: Text,
+    pub(crate) column_span: u64,
+    pub(crate) row_span: u64,
+    pub(crate) content: Body,
 }
 
 /// A [footnote][].
@@ -522,16 +730,47 @@ pub struct Cell {
 /// ```
 ///
 /// [footnote]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#footnotes
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Footnote {
-    identifier: FootnoteIdentifier,
-    body: Body,
+    pub(crate) identifier: FootnoteIdentifier,
+    pub(crate) body: Body,
 }
 
 /// An identifier of a particular [`Footnote`](struct.Footnote.html).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FootnoteIdentifier {
+    /// `[#]`
     AutoNumbered,
+    /// `[1]`
     Numbered(u64),
+    /// `[#label]`, which is numbered automatically.
     Labelled(String),
+    /// `[*]`
+    AutoSymbol,
+}
+
+impl FootnoteIdentifier {
+    /// The label of the footnote is assigned automatically.
+    pub fn is_auto(&self) -> bool {
+        !matches!(self, FootnoteIdentifier::Numbered(_))
+    }
+
+    /// The label shown for the footnote.
+    ///
+    /// Automatic labels are assigned by `sequence_number`, the position of the footnote counting
+    /// from zero among those labelled in the same way: auto-symbol footnotes count separately
+    /// from auto-numbered ones.
+    pub fn label(&self, sequence_number: usize) -> String {
+        match self {
+            FootnoteIdentifier::AutoNumbered | FootnoteIdentifier::Labelled(_) => {
+                (sequence_number + 1).to_string()
+            }
+            FootnoteIdentifier::Numbered(number) => number.to_string(),
+            FootnoteIdentifier::AutoSymbol => auto_symbol(sequence_number),
+        }
+    }
 }
 
 /// A [citation][].
@@ -550,9 +789,11 @@ pub enum FootnoteIdentifier {
 ///
 /// [citation]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#citations
 /// [reference names]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#reference-names
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Citation {
-    name: String,
-    body: Body,
+    pub(crate) name: String,
+    pub(crate) body: Body,
 }
 
 /// A [hyperlink target][].
@@ -572,16 +813,123 @@ pub struct Citation {
 /// ```
 ///
 /// [hyperlink target]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#hyperlink-targets
-pub struct Target;
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Target {
+    pub(crate) name: Option<String>,
+    pub(crate) content: HyperlinkContent,
+    pub(crate) is_anonymous: bool,
+}
+
+impl Target {
+    /// An anonymous target, which is referred to by the next anonymous reference.
+    pub fn anonymous(content: HyperlinkContent) -> Target {
+        Target {
+            name: None,
+            content,
+            is_anonymous: true,
+        }
+    }
+
+    /// A target that is referred to by name.
+    pub fn named(name: String, content: HyperlinkContent) -> Target {
+        Target {
+            name: Some(name),
+            content,
+            is_anonymous: false,
+        }
+    }
+
+    /// The reference name of the target, or `None` if it is anonymous.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The location the target refers to.
+    pub fn content(&self) -> &HyperlinkContent {
+        &self.content
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        self.is_anonymous
+    }
+}
 
 /// The content referred to by a [`Target`](struct.Target.html).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum HyperlinkContent {
+    /// An internal target, referring to the element that follows it.
     Empty,
-    URI(Url),
-    Email(String),
+    URI(#[cfg_attr(feature = "serde", serde(with = "url_serde"))] Url),
+    Email(EmailAddress),
+    /// An indirect target, referring to another target by name.
     Reference(String),
 }
 
+/// A valid email address.
+///
+/// ```
+/// use rst::ast::EmailAddress;
+///
+/// let address: EmailAddress = "docutils-users@lists.sourceforge.net".parse()?;
+/// assert_eq!(address.as_str(), "docutils-users@lists.sourceforge.net");
+/// assert!("not an address".parse::<EmailAddress>().is_err());
+/// # Ok::<(), rst::ast::InvalidEmailAddress>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct EmailAddress(String);
+
+impl EmailAddress {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for EmailAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for EmailAddress {
+    type Err = InvalidEmailAddress;
+
+    fn from_str(address: &str) -> Result<Self, InvalidEmailAddress> {
+        EmailAddress::try_from(address.to_owned())
+    }
+}
+
+impl TryFrom<String> for EmailAddress {
+    type Error = InvalidEmailAddress;
+
+    fn try_from(address: String) -> Result<Self, InvalidEmailAddress> {
+        if fast_chemail::is_valid_email(&address) {
+            Ok(EmailAddress(address))
+        } else {
+            Err(InvalidEmailAddress { address })
+        }
+    }
+}
+
+impl From<EmailAddress> for String {
+    fn from(EmailAddress(address): EmailAddress) -> String {
+        address
+    }
+}
+
+/// The text given as an [`EmailAddress`](struct.EmailAddress.html) is not a valid address.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{address:?} is not a valid email address")]
+pub struct InvalidEmailAddress {
+    address: String,
+}
+
 /// A [directive][].
 ///
 /// Directives are an extension mechanism for reStructuredText, a way of adding support for new
@@ -606,16 +954,65 @@ pub enum HyperlinkContent {
 /// ```
 ///
 /// [directive]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#directives
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Directive {
-    marker: String,
-    fields: FieldList,
-    content: DirectiveContent,
+    pub(crate) marker: String,
+    pub(crate) fields: FieldList,
+    pub(crate) content: DirectiveContent,
+}
+
+impl Directive {
+    /// The options of the directive as pairs of normalised names and their values.
+    ///
+    /// Options are kept as written, whether or not the directive recognises them. Options without
+    /// a value, or with a value that is not simple text, have no value.
+    pub fn options(&self) -> impl Iterator<Item = (String, Option<&Text>)> {
+        self.fields
+            .0
+            .iter()
+            .map(|field| (field.name(), field.text()))
+    }
+
+    /// Whether the directive has an option with the given name.
+    pub fn has_option(&self, name: &str) -> bool {
+        self.field(name).is_some()
+    }
+
+    /// The value of an option.
+    ///
+    /// The name is normalised before it is compared, so `Width` finds the option `:width:`.
+    pub fn option(&self, name: &str) -> Option<&Text> {
+        self.field(name)?.text()
+    }
+
+    /// The value of an option as plain text with surrounding whitespace removed.
+    pub fn option_as_str(&self, name: &str) -> Option<String> {
+        Some(self.option(name)?.plain_text().trim().to_owned())
+    }
+
+    /// The value of an option as an integer.
+    pub fn option_as_int(&self, name: &str) -> Option<i64> {
+        self.option_as_str(name)?.parse().ok()
+    }
+
+    /// The value of an option as a unit of measure.
+    pub fn option_as_unit(&self, name: &str) -> Option<Unit> {
+        Unit::parse(&self.option_as_str(name)?)
+    }
+
+    fn field(&self, name: &str) -> Option<&Field> {
+        let name = names::normalize_name(name);
+        self.fields.0.iter().find(|field| field.name() == name)
+    }
 }
 
 /// The content of a [`Directive`](struct.Directive.html).
 ///
 /// As some directives may want their content to be pre-processed as part of the
 /// document, the contents may need to be processed to become part of the syntax tree.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DirectiveContent {
     Literal(String),
     Parsed(Body),
@@ -635,9 +1032,11 @@ pub enum DirectiveContent {
 /// ```
 ///
 /// [substitution definition]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#substitution-definitions
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Substitution {
-    text: String,
-    directive: Directive,
+    pub(crate) text: String,
+    pub(crate) directive: Directive,
 }
 
 /// A [comment][].
@@ -659,11 +1058,41 @@ pub struct Substitution {
 /// ```
 ///
 /// [comment]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#comments
-pub struct Comment(String);
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Comment(pub(crate) String);
 
 /// An [inline][] item.
 ///
+/// # Stability
+///
+/// As with [`BodyBlock`](enum.BodyBlock.html), this enum is `non_exhaustive`. Renderers should
+/// fall back to the [plain text](struct.Text.html#method.plain_text) of unknown inline items.
+///
+/// ```compile_fail
+/// use rst::ast::Inline;
+///
+/// fn describe(inline: &Inline) -> &'static str {
+///     match inline {
+///         Inline::Emphasis(_) => "emphasis",
+///         Inline::Strong(_) => "strong",
+///         Inline::Interpreted(_) => "interpreted",
+///         Inline::Literal(_) => "literal",
+///         Inline::HyperlinkReference(_) => "reference",
+///         Inline::Target(_) => "target",
+///         Inline::StandaloneHyperlink(_) => "hyperlink",
+///         Inline::Unit(_) => "unit",
+///         Inline::Word(_) => "word",
+///         Inline::Character(_) => "character",
+///         Inline::Whitespace => "whitespace",
+///     }
+/// }
+/// ```
+///
 /// [inline]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#inline-markup
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
 pub enum Inline {
     Emphasis(Emphasis),
     Strong(Strong),
@@ -679,56 +1108,219 @@ pub enum Inline {
 }
 
 /// A sequence of [`Inline`](enum.Inline.html) items.
-pub struct Text(Vec<Inline>);
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Text(pub(crate) Vec<Inline>);
+
+impl Text {
+    /// Text of words separated by whitespace, without markup.
+    pub(crate) fn from_plain(text: &str) -> Text {
+        let mut inlines = Vec::new();
+        for (index, word) in text.split_whitespace().enumerate() {
+            if index != 0 {
+                inlines.push(Inline::Whitespace);
+            }
+            inlines.push(Inline::Word(word.to_owned()));
+        }
+        Text(inlines)
+    }
+
+    /// The text with all markup removed.
+    pub fn plain_text(&self) -> String {
+        let mut text = String::new();
+        self.push_plain_text(&mut text);
+        text
+    }
+
+    fn push_plain_text(&self, text: &mut String) {
+        for inline in &self.0 {
+            match inline {
+                Inline::Emphasis(Emphasis(inner)) | Inline::Strong(Strong(inner)) => {
+                    inner.push_plain_text(text)
+                }
+                Inline::Interpreted(interpreted) => text.push_str(&interpreted.text),
+                Inline::Literal(Literal(literal)) => text.push_str(literal),
+                Inline::HyperlinkReference(reference) => reference.label.push_plain_text(text),
+                Inline::Target(InlineInternalTarget(target)) => target.push_plain_text(text),
+                Inline::StandaloneHyperlink(StandaloneHyperlink(url)) => {
+                    text.push_str(url.as_str())
+                }
+                Inline::Word(word) => text.push_str(word),
+                Inline::Character(c) => text.push(*c),
+                Inline::Unit(unit) => text.push_str(&unit.to_string()),
+                Inline::Whitespace => text.push(' '),
+            }
+        }
+    }
+
+    /// Generate a slug from the plain text, for use in identifiers and file names.
+    pub fn slug(&self, options: &SlugOptions) -> String {
+        names::slug(&self.plain_text(), options)
+    }
+}
+
+/// Text of words separated by whitespace, without markup.
+impl From<&str> for Text {
+    fn from(text: &str) -> Text {
+        Text::from_plain(text)
+    }
+}
+
+impl From<String> for Text {
+    fn from(text: String) -> Text {
+        Text::from_plain(&text)
+    }
+}
 
 /// Text [emphasis][].
 ///
 /// [emphasis]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#emphasis
-pub struct Emphasis;
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Emphasis(pub(crate) Text);
+
+impl Emphasis {
+    /// The emphasised text.
+    pub fn text(&self) -> &Text {
+        &self.0
+    }
+}
 
 /// [Strong][] text emphasis.
 ///
 /// [strong]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#strong-emphasis
-pub struct Strong;
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Strong(pub(crate) Text);
+
+impl Strong {
+    /// The strongly emphasised text.
+    pub fn text(&self) -> &Text {
+        &self.0
+    }
+}
 
 /// [Interpreted][] text.
 ///
 /// [interpreted]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#interpreted-text
-pub struct Interpreted;
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Interpreted {
+    pub(crate) role: Option<String>,
+    pub(crate) text: String,
+}
+
+impl Interpreted {
+    /// The role given explicitly with the text, if any.
+    pub fn role(&self) -> Option<&str> {
+        self.role.as_deref()
+    }
+
+    /// The text to be interpreted by the role.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
 
 /// An inline [literal][].
 ///
 /// [literal]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#inline-literals
-pub struct Literal;
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Literal(pub(crate) String);
+
+impl Literal {
+    /// The literal text, exactly as written.
+    pub fn text(&self) -> &str {
+        &self.0
+    }
+}
 
 /// A [hyperlink reference][].
 ///
 /// [hyperlink reference]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#hyperlink-references
-pub struct HyperlinkReference;
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HyperlinkReference {
+    pub(crate) label: Text,
+    pub(crate) target: Option<String>,
+}
+
+impl HyperlinkReference {
+    /// The text of the reference as displayed.
+    pub fn label(&self) -> &Text {
+        &self.label
+    }
+
+    /// The embedded target of the reference, if it names one other than its label.
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+}
 
 /// An [inline internal target][].
 ///
 /// [inline internal target]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#inline-internal-targets
-pub struct InlineInternalTarget;
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InlineInternalTarget(pub(crate) Text);
+
+impl InlineInternalTarget {
+    /// The text of the target, which also forms its name.
+    pub fn text(&self) -> &Text {
+        &self.0
+    }
+}
 
 /// A [footnote reference][].
 ///
 /// [footnote reference]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#footnote-references
-pub struct FootnoteReference;
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FootnoteReference(pub(crate) FootnoteIdentifier);
+
+impl FootnoteReference {
+    /// The footnote being referred to.
+    pub fn identifier(&self) -> &FootnoteIdentifier {
+        &self.0
+    }
+}
 
 /// A [substitution reference][].
 ///
 /// [substitution reference]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#substitution-references
-pub struct SubstitutionReference;
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SubstitutionReference(pub(crate) Text);
+
+impl SubstitutionReference {
+    /// The text naming the substitution.
+    pub fn text(&self) -> &Text {
+        &self.0
+    }
+}
 
 /// A [standalone hyperlink][].
 ///
 /// [standalone hyperlink]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#standalone-hyperlinks
-pub struct StandaloneHyperlink;
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StandaloneHyperlink(
+    #[cfg_attr(feature = "serde", serde(with = "url_serde"))] pub(crate) Url,
+);
+
+impl StandaloneHyperlink {
+    /// The address of the link.
+    pub fn url(&self) -> &Url {
+        &self.0
+    }
+}
 
 /// A [unit][] of measure;
 ///
 /// [unit]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#units
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Unit {
     Em(f64),
     Ex(f64),
@@ -740,3 +1332,247 @@ pub enum Unit {
     Pica(f64),
     Percent(f64),
 }
+
+impl Unit {
+    /// Parse a length or percentage, such as `300px`, `2.5em` or `50%`.
+    ///
+    /// A number without a unit is a length in pixels.
+    pub fn parse(text: &str) -> Option<Unit> {
+        let text = text.trim();
+        let split = text
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+            .unwrap_or(text.len());
+        let (number, unit) = text.split_at(split);
+        let number: f64 = number.parse().ok()?;
+
+        let unit = match unit.trim() {
+            "em" => Unit::Em(number),
+            "ex" => Unit::Ex(number),
+            "mm" => Unit::Millimeter(number),
+            "cm" => Unit::Centimeter(number),
+            "in" => Unit::Inch(number),
+            "px" | "" => Unit::Pixel(number),
+            "pt" => Unit::Point(number),
+            "pc" => Unit::Pica(number),
+            "%" => Unit::Percent(number),
+            _ => return None,
+        };
+
+        Some(unit)
+    }
+}
+
+/// Formats the unit as it is [parsed](#method.parse), such as `2.5em`.
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unit::Em(value) => write!(f, "{}em", value),
+            Unit::Ex(value) => write!(f, "{}ex", value),
+            Unit::Millimeter(value) => write!(f, "{}mm", value),
+            Unit::Centimeter(value) => write!(f, "{}cm", value),
+            Unit::Inch(value) => write!(f, "{}in", value),
+            Unit::Pixel(value) => write!(f, "{}px", value),
+            Unit::Point(value) => write!(f, "{}pt", value),
+            Unit::Pica(value) => write!(f, "{}pc", value),
+            Unit::Percent(value) => write!(f, "{}%", value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, value: Option<&str>) -> Field {
+        let blocks = value
+            .map(|value| BodyBlock::Paragraph(Paragraph(Text::from_plain(value))))
+            .into_iter()
+            .collect();
+        Field {
+            marker: Text::from_plain(name),
+            body: Body(blocks),
+        }
+    }
+
+    #[test]
+    fn inline_plain_text() {
+        let mut inlines = Text::from_plain("see").0;
+        inlines.push(Inline::Whitespace);
+        inlines.push(Inline::Strong(Strong(Text(vec![
+            Inline::Emphasis(Emphasis(Text::from_plain("nested emphasis"))),
+            Inline::Whitespace,
+            Inline::Literal(Literal("code()".to_owned())),
+        ]))));
+        inlines.push(Inline::Whitespace);
+        inlines.push(Inline::HyperlinkReference(HyperlinkReference {
+            label: Text::from_plain("the docs"),
+            target: Some("docs".to_owned()),
+        }));
+        inlines.push(Inline::Character(':'));
+        inlines.push(Inline::Whitespace);
+        inlines.push(Inline::StandaloneHyperlink(StandaloneHyperlink(
+            Url::parse("http://example.com/").unwrap(),
+        )));
+        inlines.push(Inline::Whitespace);
+        inlines.push(Inline::Unit(Unit::Em(1.5)));
+
+        assert_eq!(
+            Text(inlines).plain_text(),
+            "see nested emphasis code() the docs: http://example.com/ 1.5em"
+        );
+    }
+
+    #[test]
+    fn unknown_directive_options() {
+        let directive = Directive {
+            marker: "custom-widget".to_owned(),
+            fields: FieldList(vec![
+                field("Width", Some("300px")),
+                field("max  items", Some("12")),
+                field("caption", Some("A small widget")),
+                field("scale", Some("50%")),
+                field("linenos", None),
+            ]),
+            content: DirectiveContent::Literal(String::new()),
+        };
+
+        let names: Vec<_> = directive.options().map(|(name, _)| name).collect();
+        assert_eq!(
+            names,
+            &["width", "max items", "caption", "scale", "linenos"]
+        );
+
+        assert_eq!(directive.option_as_unit("width"), Some(Unit::Pixel(300.0)));
+        assert_eq!(directive.option_as_unit("WIDTH"), Some(Unit::Pixel(300.0)));
+        assert_eq!(directive.option_as_int("Max Items"), Some(12));
+        assert_eq!(
+            directive.option_as_str("caption").as_deref(),
+            Some("A small widget")
+        );
+        assert_eq!(directive.option_as_unit("scale"), Some(Unit::Percent(50.0)));
+        assert_eq!(directive.option_as_int("caption"), None);
+
+        assert!(directive.has_option("linenos"));
+        assert!(directive.option("linenos").is_none());
+        assert!(!directive.has_option("height"));
+    }
+
+    #[test]
+    fn parse_documents() {
+        let text = "Title\n=====\n\n- One.\n- Two.\n";
+        let document: Document = text.parse().unwrap();
+        assert_eq!(Document::try_from(text).unwrap(), document);
+        assert_eq!(
+            Document::from_reader("reader", text.as_bytes()).unwrap(),
+            document
+        );
+
+        let error = ".. note:: Text.\n".parse::<Document>().unwrap_err();
+        assert!(error.to_string().starts_with("<string>["), "{}", error);
+        let error = Document::from_reader("index.rst", ".. note:: Text.\n".as_bytes()).unwrap_err();
+        assert!(error.to_string().starts_with("index.rst["), "{}", error);
+    }
+
+    #[test]
+    fn targets() {
+        let url = Url::parse("http://www.python.org/").unwrap();
+        let target = Target::named("Python".to_owned(), HyperlinkContent::URI(url.clone()));
+        assert_eq!(target.name(), Some("Python"));
+        assert!(!target.is_anonymous());
+        assert_eq!(target.content(), &HyperlinkContent::URI(url));
+
+        let address: EmailAddress = "user@example.com".parse().unwrap();
+        let target = Target::anonymous(HyperlinkContent::Email(address));
+        assert_eq!(target.name(), None);
+        assert!(target.is_anonymous());
+        match target.content() {
+            HyperlinkContent::Email(address) => assert_eq!(address.to_string(), "user@example.com"),
+            content => panic!("unexpected content: {:?}", content),
+        }
+
+        for invalid in &["", "user", "user@", "@example.com", "two@at@example.com"] {
+            let error = invalid.parse::<EmailAddress>().unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!("{:?} is not a valid email address", invalid)
+            );
+        }
+    }
+
+    #[test]
+    fn list_markers() {
+        assert_eq!(ListMarker::Arabic(3).resolved_number(0), Some(3));
+        assert_eq!(ListMarker::Arabic(3).resolved_number(2), Some(5));
+        assert_eq!(ListMarker::AutoNumbered.resolved_number(0), Some(1));
+        assert_eq!(ListMarker::AutoNumbered.resolved_number(9), Some(10));
+        assert_eq!(ListMarker::RomanLowercase(4).resolved_number(1), Some(5));
+        assert_eq!(ListMarker::Arabic(u64::MAX).resolved_number(1), None);
+        assert_eq!(ListMarker::Bullet.resolved_number(0), None);
+        assert_eq!(ListMarker::AutoSymbol.resolved_number(0), None);
+
+        let symbols: Vec<_> = (0..12)
+            .map(|index| ListMarker::AutoSymbol.resolved_symbol(index).unwrap())
+            .collect();
+        assert_eq!(
+            symbols,
+            ["*", "†", "‡", "§", "¶", "#", "♠", "♥", "♦", "♣", "**", "††"]
+        );
+        assert_eq!(ListMarker::AutoNumbered.resolved_symbol(0), None);
+    }
+
+    #[test]
+    fn footnote_labels() {
+        let labelled = FootnoteIdentifier::Labelled("note".to_owned());
+        assert!(FootnoteIdentifier::AutoNumbered.is_auto());
+        assert!(FootnoteIdentifier::AutoSymbol.is_auto());
+        assert!(labelled.is_auto());
+        assert!(!FootnoteIdentifier::Numbered(3).is_auto());
+
+        assert_eq!(FootnoteIdentifier::AutoNumbered.label(0), "1");
+        assert_eq!(labelled.label(1), "2");
+        assert_eq!(FootnoteIdentifier::Numbered(3).label(0), "3");
+        assert_eq!(FootnoteIdentifier::AutoSymbol.label(0), "*");
+        assert_eq!(FootnoteIdentifier::AutoSymbol.label(9), "♣");
+        assert_eq!(FootnoteIdentifier::AutoSymbol.label(10), "**");
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(Unit::parse("2.5em"), Some(Unit::Em(2.5)));
+        assert_eq!(Unit::parse("10 mm"), Some(Unit::Millimeter(10.0)));
+        assert_eq!(Unit::parse("12"), Some(Unit::Pixel(12.0)));
+        assert_eq!(Unit::parse("1in"), Some(Unit::Inch(1.0)));
+        assert_eq!(Unit::parse("wide"), None);
+        assert_eq!(Unit::parse("3furlongs"), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use crate::location::TextSource;
+        use crate::{Parser, TokenStream};
+
+        let mut source = TextSource::from_str(
+            "serde",
+            "Title\n=====\n\n* Item::\n\n    literal\n\n.. [#] Note.\n",
+        );
+        let mut document = Parser::new(TokenStream::try_new(&mut source).unwrap())
+            .parse()
+            .unwrap();
+        let link = Url::parse("https://docutils.sourceforge.io/").unwrap();
+        document
+            .0
+            .push(SectionChildren::Body(BodyBlock::Paragraph(Paragraph(
+                Text(vec![
+                    Inline::StandaloneHyperlink(StandaloneHyperlink(link)),
+                    Inline::Whitespace,
+                    Inline::Unit(Unit::Em(2.5)),
+                ]),
+            ))));
+
+        let json = serde_json::to_string(&document).unwrap();
+        assert!(json.contains("\"https://docutils.sourceforge.io/\""));
+        let deserialized: Document = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, document);
+    }
+}
@@ -0,0 +1,543 @@
+//! Construction and normalisation of tables.
+//!
+//! Rows and columns are numbered from zero, with header rows before body rows.
+
+use thiserror::Error;
+
+use super::*;
+
+/// The most columns a table is taken to have, so that a cell spanning an unreasonable number of
+/// columns cannot exhaust memory when the table is normalised.
+const MAX_COLUMNS: usize = u16::MAX as usize;
+
+/// Builds a [`Table`](struct.Table.html), checking that its cells fill a rectangular grid.
+///
+/// Cells are added to the current row from left to right, skipping positions covered by cells
+/// from earlier rows that span multiple rows.
+///
+/// ```
+/// use rst::ast::TableBuilder;
+///
+/// let table = TableBuilder::new(2)
+///     .header_row()
+///     .cell("Name")
+///     .cell("Value")
+///     .row()
+///     .cell("Cells may span columns.")
+///     .span_cols(2)
+///     .build()?;
+/// assert_eq!(table.body()[0].cells().len(), 1);
+/// # Ok::<(), rst::ast::TableError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct TableBuilder {
+    columns: usize,
+    header: Vec<Row>,
+    body: Vec<Row>,
+    error: Option<TableError>,
+}
+
+/// A table built with a [`TableBuilder`](struct.TableBuilder.html) does not form a grid.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TableError {
+    #[error("a cell was added before the first row")]
+    CellWithoutRow,
+    #[error("a span was set before the first cell of row {row}")]
+    SpanWithoutCell { row: usize },
+    #[error("header row {row} follows a body row")]
+    HeaderAfterBody { row: usize },
+    #[error("the cell at row {row}, column {column} spans no rows or columns")]
+    ZeroSpan { row: usize, column: usize },
+    #[error("the cell at row {row}, column {column} extends beyond the last column")]
+    TooManyColumns { row: usize, column: usize },
+    #[error("row {row} has no cell at column {column}")]
+    MissingColumn { row: usize, column: usize },
+    #[error("the cell at row {row}, column {column} extends beyond the last row")]
+    TooManyRows { row: usize, column: usize },
+}
+
+impl TableBuilder {
+    /// Build a table with the given number of columns.
+    pub fn new(columns: usize) -> Self {
+        TableBuilder {
+            columns,
+            header: Vec::new(),
+            body: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Start a header row.
+    pub fn header_row(&mut self) -> &mut Self {
+        if !self.body.is_empty() {
+            let row = self.header.len() + self.body.len();
+            self.fail(TableError::HeaderAfterBody { row });
+        }
+        self.header.push(Row(Vec::new()));
+        self
+    }
+
+    /// Start a body row.
+    pub fn row(&mut self) -> &mut Self {
+        self.body.push(Row(Vec::new()));
+        self
+    }
+
+    /// Add a cell holding a single paragraph to the current row, spanning a single row and column.
+    ///
+    /// A cell with blank text is empty.
+    pub fn cell(&mut self, text: &str) -> &mut Self {
+        let content = match Text::from_plain(text) {
+            Text(inlines) if inlines.is_empty() => Body(Vec::new()),
+            text => Body(vec![BodyBlock::Paragraph(Paragraph(text))]),
+        };
+        match self.current_row() {
+            Some(Row(cells)) => cells.push(Cell {
+                column_span: 1,
+                row_span: 1,
+                content,
+            }),
+            None => self.fail(TableError::CellWithoutRow),
+        }
+        self
+    }
+
+    /// Set the number of columns spanned by the last cell.
+    pub fn span_cols(&mut self, columns: u64) -> &mut Self {
+        self.with_last_cell(|cell| cell.column_span = columns)
+    }
+
+    /// Set the number of rows spanned by the last cell.
+    pub fn span_rows(&mut self, rows: u64) -> &mut Self {
+        self.with_last_cell(|cell| cell.row_span = rows)
+    }
+
+    /// The table built so far.
+    ///
+    /// Fails with the first problem found, in the order that cells were added.
+    pub fn build(&self) -> Result<Table, TableError> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+
+        let table = Table {
+            header: self.header.clone(),
+            body: self.body.clone(),
+        };
+        check_grid(&table, self.columns)?;
+        Ok(table)
+    }
+
+    fn current_row(&mut self) -> Option<&mut Row> {
+        match self.body.last_mut() {
+            Some(row) => Some(row),
+            None => self.header.last_mut(),
+        }
+    }
+
+    fn with_last_cell(&mut self, update: impl FnOnce(&mut Cell)) -> &mut Self {
+        let row = (self.header.len() + self.body.len()).saturating_sub(1);
+        match self.current_row().and_then(|Row(cells)| cells.last_mut()) {
+            Some(cell) => update(cell),
+            None => self.fail(TableError::SpanWithoutCell { row }),
+        }
+        self
+    }
+
+    fn fail(&mut self, error: TableError) {
+        self.error.get_or_insert(error);
+    }
+}
+
+/// A position in the grid covered by a cell from an earlier row.
+#[derive(Clone, Copy, Default)]
+struct Covered {
+    /// The number of rows still covered, including the current row.
+    rows: u64,
+    /// The position of the cell covering this one.
+    origin: (usize, usize),
+}
+
+/// Check that the cells of the table fill a grid of the given number of columns exactly.
+fn check_grid(table: &Table, columns: usize) -> Result<(), TableError> {
+    let mut covered = vec![Covered::default(); columns];
+
+    for (row, Row(cells)) in table.rows().enumerate() {
+        let mut column = 0;
+        for cell in cells {
+            while column < columns && covered[column].rows > 0 {
+                column += 1;
+            }
+            if cell.column_span == 0 || cell.row_span == 0 {
+                return Err(TableError::ZeroSpan { row, column });
+            }
+
+            let end = match span(cell).filter(|&span| span <= columns - column) {
+                Some(span) => column + span,
+                None => return Err(TableError::TooManyColumns { row, column }),
+            };
+            if covered[column..end].iter().any(|c| c.rows > 0) {
+                return Err(TableError::TooManyColumns { row, column });
+            }
+            for position in &mut covered[column..end] {
+                *position = Covered {
+                    rows: cell.row_span,
+                    origin: (row, column),
+                };
+            }
+            column = end;
+        }
+
+        if let Some(column) = covered.iter().position(|c| c.rows == 0) {
+            return Err(TableError::MissingColumn { row, column });
+        }
+        for position in &mut covered {
+            position.rows -= 1;
+        }
+    }
+
+    match covered.iter().find(|c| c.rows > 0) {
+        Some(Covered {
+            origin: (row, column),
+            ..
+        }) => Err(TableError::TooManyRows {
+            row: *row,
+            column: *column,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The number of columns spanned by a cell, if it can be held in memory.
+fn span(cell: &Cell) -> Option<usize> {
+    usize::try_from(cell.column_span).ok()
+}
+
+impl Table {
+    /// The header rows.
+    pub fn header(&self) -> &[Row] {
+        &self.header
+    }
+
+    /// The body rows.
+    pub fn body(&self) -> &[Row] {
+        &self.body
+    }
+
+    /// The header rows followed by the body rows.
+    pub fn rows(&self) -> impl Iterator<Item = &Row> {
+        self.header.iter().chain(&self.body)
+    }
+
+    /// Make every row hold one cell for each column.
+    ///
+    /// Positions covered by spanning cells are filled with
+    /// [covered](struct.Cell.html#method.is_covered) cells, and rows that are too short are
+    /// completed with empty cells, so that the cells of each row line up with the columns of the
+    /// table. Normalising a table more than once has no further effect.
+    pub fn normalize(&mut self) {
+        let width = self.width();
+        let mut covered = vec![0; width];
+
+        for Row(cells) in self.header.iter_mut().chain(&mut self.body) {
+            let mut spanning = std::mem::take(cells)
+                .into_iter()
+                .filter(|cell| !cell.is_covered());
+            let mut column = 0;
+
+            while column < width {
+                if covered[column] > 0 {
+                    cells.push(Cell::covered());
+                    column += 1;
+                    continue;
+                }
+
+                let cell = spanning.next().unwrap_or_else(Cell::empty);
+                let span = span(&cell).unwrap_or(usize::MAX).max(1);
+                let end = column.saturating_add(span).min(width);
+                for position in &mut covered[column..end] {
+                    *position = cell.row_span.max(1);
+                }
+                cells.push(cell);
+                cells.extend((column + 1..end).map(|_| Cell::covered()));
+                column = end;
+            }
+
+            for position in &mut covered {
+                *position = position.saturating_sub(1);
+            }
+        }
+    }
+
+    /// The number of columns needed to hold every row, up to `MAX_COLUMNS`.
+    fn width(&self) -> usize {
+        let mut covered: Vec<u64> = Vec::new();
+        for Row(cells) in self.rows() {
+            let mut column = 0;
+            for cell in cells.iter().filter(|cell| !cell.is_covered()) {
+                while covered.get(column).is_some_and(|&rows| rows > 0) {
+                    column += 1;
+                }
+                let span = span(cell).unwrap_or(usize::MAX).max(1);
+                let end = column.saturating_add(span).min(MAX_COLUMNS);
+                if covered.len() < end {
+                    covered.resize(end, 0);
+                }
+                for position in &mut covered[column..end] {
+                    *position = cell.row_span.max(1);
+                }
+                column = end;
+            }
+            for position in &mut covered {
+                *position = position.saturating_sub(1);
+            }
+        }
+        covered.len()
+    }
+}
+
+impl Row {
+    /// The cells of the row, from left to right.
+    pub fn cells(&self) -> &[Cell] {
+        &self.0
+    }
+}
+
+impl Cell {
+    /// The number of columns spanned by the cell.
+    pub fn column_span(&self) -> u64 {
+        self.column_span
+    }
+
+    /// The number of rows spanned by the cell.
+    pub fn row_span(&self) -> u64 {
+        self.row_span
+    }
+
+    /// The body elements within the cell.
+    pub fn content(&self) -> &Body {
+        &self.content
+    }
+
+    /// The cell is a position covered by a spanning cell in a
+    /// [normalised](struct.Table.html#method.normalize) table, and should not be rendered.
+    pub fn is_covered(&self) -> bool {
+        self.column_span == 0 && self.row_span == 0
+    }
+
+    fn covered() -> Cell {
+        Cell {
+            column_span: 0,
+            row_span: 0,
+            content: Body(Vec::new()),
+        }
+    }
+
+    fn empty() -> Cell {
+        Cell {
+            column_span: 1,
+            row_span: 1,
+            content: Body(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::write::HtmlWriter;
+
+    /// The grid table from the documentation of [`Table`](../struct.Table.html).
+    fn grid_table() -> TableBuilder {
+        let mut builder = TableBuilder::new(4);
+        builder
+            .header_row()
+            .cell("Header row, column 1 (header rows optional)")
+            .cell("Header 2")
+            .cell("Header 3")
+            .cell("Header 4")
+            .row()
+            .cell("body row 1, column 1")
+            .cell("column 2")
+            .cell("column 3")
+            .cell("column 4")
+            .row()
+            .cell("body row 2")
+            .cell("Cells may span columns.")
+            .span_cols(3)
+            .row()
+            .cell("body row 3")
+            .cell("Cells may span rows.")
+            .span_rows(2)
+            .cell("Table cells contain body elements.")
+            .span_cols(2)
+            .span_rows(2)
+            .row()
+            .cell("body row 4");
+        builder
+    }
+
+    fn html(table: Table) -> String {
+        let document = Document(vec![SectionChildren::Body(BodyBlock::Table(table))]);
+        let mut output = Vec::new();
+        HtmlWriter::default()
+            .write_document(&document, &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn build_grid_table() {
+        let cell = |text: &str, column_span, row_span| Cell {
+            column_span,
+            row_span,
+            content: Body(vec![BodyBlock::Paragraph(Paragraph(Text::from_plain(
+                text,
+            )))]),
+        };
+        let expected = Table {
+            header: vec![Row(vec![
+                cell("Header row, column 1 (header rows optional)", 1, 1),
+                cell("Header 2", 1, 1),
+                cell("Header 3", 1, 1),
+                cell("Header 4", 1, 1),
+            ])],
+            body: vec![
+                Row(vec![
+                    cell("body row 1, column 1", 1, 1),
+                    cell("column 2", 1, 1),
+                    cell("column 3", 1, 1),
+                    cell("column 4", 1, 1),
+                ]),
+                Row(vec![
+                    cell("body row 2", 1, 1),
+                    cell("Cells may span columns.", 3, 1),
+                ]),
+                Row(vec![
+                    cell("body row 3", 1, 1),
+                    cell("Cells may span rows.", 1, 2),
+                    cell("Table cells contain body elements.", 2, 2),
+                ]),
+                Row(vec![cell("body row 4", 1, 1)]),
+            ],
+        };
+
+        let table = grid_table().build().unwrap();
+        let rendered = html(table);
+        assert_eq!(rendered, html(expected));
+        assert!(rendered.contains("<td colspan=\"2\" rowspan=\"2\">Table cells"));
+
+        let mut table = grid_table().build().unwrap();
+        table.normalize();
+        assert!(table.rows().all(|row| row.cells().len() == 4));
+        let covered: Vec<Vec<bool>> = table
+            .rows()
+            .map(|row| row.cells().iter().map(Cell::is_covered).collect())
+            .collect();
+        assert_eq!(
+            covered[2..],
+            [
+                [false, false, true, true],
+                [false, false, false, true],
+                [false, true, true, true],
+            ]
+        );
+        // Covered cells are not rendered, so normalising does not change the output.
+        assert_eq!(html(table), rendered);
+    }
+
+    #[test]
+    fn normalize_ragged_rows() {
+        let mut table = Table {
+            header: vec![],
+            body: vec![
+                Row(vec![Cell::empty(), Cell::empty(), Cell::empty()]),
+                Row(vec![Cell::empty()]),
+            ],
+        };
+        table.normalize();
+        let lengths: Vec<_> = table.rows().map(|row| row.cells().len()).collect();
+        assert_eq!(lengths, [3, 3]);
+
+        table.normalize();
+        let lengths: Vec<_> = table.rows().map(|row| row.cells().len()).collect();
+        assert_eq!(lengths, [3, 3]);
+    }
+
+    #[test]
+    fn normalize_huge_spans() {
+        let mut wide = Cell::empty();
+        wide.column_span = u64::MAX;
+        let mut table = Table {
+            header: vec![],
+            body: vec![Row(vec![Cell::empty(), wide]), Row(vec![Cell::empty()])],
+        };
+        table.normalize();
+        let lengths: Vec<_> = table.rows().map(|row| row.cells().len()).collect();
+        assert_eq!(lengths, [MAX_COLUMNS, MAX_COLUMNS]);
+    }
+
+    #[test]
+    fn grid_errors() {
+        let error = |builder: &mut TableBuilder| match builder.build() {
+            Ok(_) => panic!("the table was built"),
+            Err(error) => error,
+        };
+
+        assert_eq!(
+            error(TableBuilder::new(2).cell("a")),
+            TableError::CellWithoutRow
+        );
+        assert_eq!(
+            error(TableBuilder::new(2).row().span_cols(2)),
+            TableError::SpanWithoutCell { row: 0 }
+        );
+        assert_eq!(
+            error(TableBuilder::new(1).row().cell("a").header_row()),
+            TableError::HeaderAfterBody { row: 1 }
+        );
+        assert_eq!(
+            error(TableBuilder::new(2).row().cell("a").cell("b").span_rows(0)),
+            TableError::ZeroSpan { row: 0, column: 1 }
+        );
+        assert_eq!(
+            error(TableBuilder::new(2).row().cell("a").cell("b").span_cols(2)),
+            TableError::TooManyColumns { row: 0, column: 1 }
+        );
+        assert_eq!(
+            error(
+                TableBuilder::new(2)
+                    .row()
+                    .cell("a")
+                    .cell("b")
+                    .span_cols(u64::MAX)
+            ),
+            TableError::TooManyColumns { row: 0, column: 1 }
+        );
+        assert_eq!(
+            error(
+                TableBuilder::new(2)
+                    .row()
+                    .cell("a")
+                    .span_rows(2)
+                    .cell("b")
+                    .row()
+                    .cell("c")
+                    .cell("d")
+            ),
+            TableError::TooManyColumns { row: 1, column: 2 }
+        );
+        assert_eq!(
+            error(grid_table().row().cell("body row 5").cell("short")),
+            TableError::MissingColumn { row: 5, column: 2 }
+        );
+        assert_eq!(
+            error(TableBuilder::new(2).row().cell("a").cell("b").span_rows(3)),
+            TableError::TooManyRows { row: 0, column: 1 }
+        );
+        assert_eq!(
+            TableError::MissingColumn { row: 5, column: 2 }.to_string(),
+            "row 5 has no cell at column 2"
+        );
+    }
+}
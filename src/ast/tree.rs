@@ -0,0 +1,371 @@
+//! Compact rendering of the syntax tree for inspection.
+//!
+//! Each node is written on its own line, indented to show nesting, with text summarised rather
+//! than reproduced in full.
+
+use std::fmt::Write;
+
+use super::*;
+
+/// The maximum number of characters of text shown for a single node.
+const EXCERPT_LENGTH: usize = 40;
+
+/// Render a document as an indented tree.
+pub(super) fn document(document: &Document) -> String {
+    let mut printer = TreePrinter::default();
+    printer.line("document");
+    printer.nested(|printer| printer.children(&document.0));
+    printer.output
+}
+
+#[derive(Default)]
+struct TreePrinter {
+    output: String,
+    depth: usize,
+}
+
+impl TreePrinter {
+    fn line(&mut self, label: &str) {
+        for _ in 0..self.depth {
+            self.output.push_str("  ");
+        }
+        self.output.push_str(label);
+        self.output.push('\n');
+    }
+
+    fn nested(&mut self, print: impl FnOnce(&mut Self)) {
+        self.depth += 1;
+        print(self);
+        self.depth -= 1;
+    }
+
+    fn body(&mut self, body: &Body) {
+        for block in &body.0 {
+            self.block(block);
+        }
+    }
+
+    fn section(&mut self, section: &Section) {
+        self.line(&format!("section {}", excerpt(&section.title)));
+        self.nested(|printer| printer.children(&section.children));
+    }
+
+    fn children(&mut self, children: &[SectionChildren]) {
+        for child in children {
+            match child {
+                SectionChildren::Body(block) => self.block(block),
+                SectionChildren::Transition => self.line("transition"),
+                SectionChildren::Section(section) => self.section(section),
+            }
+        }
+    }
+
+    fn block(&mut self, block: &BodyBlock) {
+        match block {
+            BodyBlock::Paragraph(Paragraph(text)) => {
+                self.line(&format!("paragraph {}", summary(text)));
+            }
+            BodyBlock::List(list) => self.list(list),
+            BodyBlock::DefinitionList(DefinitionList(definitions)) => {
+                self.line("definition_list");
+                self.nested(|printer| {
+                    for definition in definitions {
+                        printer.definition(definition);
+                    }
+                });
+            }
+            BodyBlock::FieldList(fields) => self.field_list(fields),
+            BodyBlock::OptionList(OptionList(items)) => {
+                self.line("option_list");
+                self.nested(|printer| {
+                    for item in items {
+                        printer.option_item(item);
+                    }
+                });
+            }
+            BodyBlock::LiteralBlock(LiteralBlock(literal)) => {
+                self.line(&format!("literal_block {}", lines(literal)));
+            }
+            BodyBlock::LineBlock(LineBlock(lines)) => {
+                self.line("line_block");
+                self.nested(|printer| printer.lines(lines));
+            }
+            BodyBlock::BlockQuote(quote) => {
+                self.line("block_quote");
+                self.nested(|printer| {
+                    printer.body(&quote.quote);
+                    if let Some(attribution) = &quote.attribution {
+                        printer.line(&format!("attribution {}", summary(attribution)));
+                    }
+                });
+            }
+            BodyBlock::DocTest(DocTest(doctest)) => {
+                self.line(&format!("doctest_block {}", lines(doctest)));
+            }
+            BodyBlock::Table(table) => self.table(table),
+            BodyBlock::Footnote(footnote) => {
+                let label = match &footnote.identifier {
+                    FootnoteIdentifier::AutoNumbered => "#".to_owned(),
+                    FootnoteIdentifier::AutoSymbol => "*".to_owned(),
+                    FootnoteIdentifier::Numbered(number) => number.to_string(),
+                    FootnoteIdentifier::Labelled(label) => format!("#{}", label),
+                };
+                self.line(&format!("footnote [{}]", label));
+                self.nested(|printer| printer.body(&footnote.body));
+            }
+            BodyBlock::Citation(citation) => {
+                self.line(&format!("citation [{}]", citation.name));
+                self.nested(|printer| printer.body(&citation.body));
+            }
+            BodyBlock::Target(_) => self.line("target"),
+            BodyBlock::Directive(directive) => self.directive(directive),
+            BodyBlock::Substitution(substitution) => {
+                self.line(&format!(
+                    "substitution_definition {}",
+                    excerpt(&substitution.text)
+                ));
+                self.nested(|printer| printer.directive(&substitution.directive));
+            }
+            BodyBlock::Comment(Comment(comment)) => {
+                self.line(&format!("comment {}", lines(comment)));
+            }
+        }
+    }
+
+    fn list(&mut self, list: &List) {
+        let label = match list.marker {
+            ListMarker::Bullet => "bullet_list".to_owned(),
+            ListMarker::Arabic(start) => format!("enumerated_list arabic from {}", start),
+            ListMarker::LatinUppercase(start) => {
+                format!("enumerated_list upperalpha from {}", start)
+            }
+            ListMarker::LatinLowercase(start) => {
+                format!("enumerated_list loweralpha from {}", start)
+            }
+            ListMarker::RomanUppercase(start) => {
+                format!("enumerated_list upperroman from {}", start)
+            }
+            ListMarker::RomanLowercase(start) => {
+                format!("enumerated_list lowerroman from {}", start)
+            }
+            ListMarker::AutoNumbered => "enumerated_list auto".to_owned(),
+            ListMarker::AutoSymbol => "enumerated_list symbol".to_owned(),
+        };
+        self.line(&label);
+        self.nested(|printer| {
+            for element in &list.elements {
+                printer.line("list_item");
+                printer.nested(|printer| printer.body(element));
+            }
+        });
+    }
+
+    fn definition(&mut self, definition: &Definition) {
+        self.line("definition_list_item");
+        self.nested(|printer| {
+            printer.line(&format!("term {}", summary(&definition.term)));
+            for classifier in &definition.classifiers {
+                printer.line(&format!("classifier {}", summary(classifier)));
+            }
+            printer.line("definition");
+            printer.nested(|printer| printer.body(&definition.definition));
+        });
+    }
+
+    fn field_list(&mut self, FieldList(fields): &FieldList) {
+        self.line("field_list");
+        self.nested(|printer| {
+            for field in fields {
+                printer.line(&format!("field {}", excerpt(&field.marker.plain_text())));
+                printer.nested(|printer| printer.body(&field.body));
+            }
+        });
+    }
+
+    fn option_item(&mut self, item: &OptionItem) {
+        let mut options = String::new();
+        for (index, (option, argument)) in item.options.iter().enumerate() {
+            if index != 0 {
+                options.push_str(", ");
+            }
+            options.push_str(option);
+            if let Some(argument) = argument {
+                write!(options, " {}", argument).expect("writing to a string cannot fail");
+            }
+        }
+
+        self.line(&format!("option_list_item {}", excerpt(&options)));
+        self.nested(|printer| {
+            printer.line(&format!("description {}", summary(&item.description)));
+        });
+    }
+
+    fn lines(&mut self, lines: &[Line]) {
+        for line in lines {
+            self.line(&format!("line {}", summary(&line.content)));
+            self.nested(|printer| printer.lines(&line.children));
+        }
+    }
+
+    fn table(&mut self, table: &Table) {
+        self.line("table");
+        self.nested(|printer| {
+            for row in &table.header {
+                printer.row("header_row", row);
+            }
+            for row in &table.body {
+                printer.row("row", row);
+            }
+        });
+    }
+
+    fn row(&mut self, label: &str, Row(cells): &Row) {
+        self.line(label);
+        self.nested(|printer| {
+            for cell in cells.iter().filter(|cell| !cell.is_covered()) {
+                let mut label = "entry".to_owned();
+                if cell.column_span > 1 {
+                    write!(label, " ({} columns)", cell.column_span)
+                        .expect("writing to a string cannot fail");
+                }
+                if cell.row_span > 1 {
+                    write!(label, " ({} rows)", cell.row_span)
+                        .expect("writing to a string cannot fail");
+                }
+                printer.line(&label);
+                printer.nested(|printer| printer.body(&cell.content));
+            }
+        });
+    }
+
+    fn directive(&mut self, directive: &Directive) {
+        self.line(&format!("directive {}", excerpt(&directive.marker)));
+        self.nested(|printer| {
+            if !directive.fields.0.is_empty() {
+                printer.field_list(&directive.fields);
+            }
+            match &directive.content {
+                DirectiveContent::Literal(literal) if literal.is_empty() => {}
+                DirectiveContent::Literal(literal) => {
+                    printer.line(&format!("literal {}", lines(literal)));
+                }
+                DirectiveContent::Parsed(body) => printer.body(body),
+            }
+        });
+    }
+}
+
+/// Quote text, truncated to a readable length and with whitespace collapsed.
+fn excerpt(text: &str) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut excerpt: String = collapsed.chars().take(EXCERPT_LENGTH).collect();
+    if excerpt.len() < collapsed.len() {
+        excerpt.push('…');
+    }
+    format!("{:?}", excerpt)
+}
+
+/// Summarise text with an excerpt and a word count.
+fn summary(text: &Text) -> String {
+    let text = text.plain_text();
+    let words = text.split_whitespace().count();
+    let plural = if words == 1 { "" } else { "s" };
+    format!("{} [{} word{}]", excerpt(&text), words, plural)
+}
+
+/// Summarise literal text with an excerpt and a line count.
+fn lines(text: &str) -> String {
+    let lines = text.lines().count();
+    let plural = if lines == 1 { "" } else { "s" };
+    format!("{} [{} line{}]", excerpt(text), lines, plural)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paragraph(content: &str) -> BodyBlock {
+        BodyBlock::Paragraph(Paragraph(Text::from_plain(content)))
+    }
+
+    #[test]
+    fn document_tree() {
+        let document = Document(
+            vec![
+                paragraph("Install the package with pip before running any of the examples below."),
+                BodyBlock::List(List {
+                    marker: ListMarker::Arabic(1),
+                    elements: vec![
+                        Body(vec![paragraph("First item.")]),
+                        Body(vec![
+                            paragraph("Second item."),
+                            BodyBlock::List(List {
+                                marker: ListMarker::Bullet,
+                                elements: vec![Body(vec![paragraph("Nested")])],
+                            }),
+                        ]),
+                    ],
+                }),
+                BodyBlock::LiteralBlock(LiteralBlock(
+                    "for a in [5,4,3,2,1]:\n    print a".to_owned(),
+                )),
+                BodyBlock::BlockQuote(BlockQuote {
+                    quote: Body(vec![paragraph("It is my business to know things.")]),
+                    attribution: Some(Text::from_plain("Sherlock Holmes")),
+                }),
+                BodyBlock::Comment(Comment("A comment".to_owned())),
+            ]
+            .into_iter()
+            .map(SectionChildren::Body)
+            .collect(),
+        );
+
+        assert_eq!(
+            document.tree_string(),
+            concat!(
+                "document\n",
+                "  paragraph \"Install the package with pip before runn…\" [12 words]\n",
+                "  enumerated_list arabic from 1\n",
+                "    list_item\n",
+                "      paragraph \"First item.\" [2 words]\n",
+                "    list_item\n",
+                "      paragraph \"Second item.\" [2 words]\n",
+                "      bullet_list\n",
+                "        list_item\n",
+                "          paragraph \"Nested\" [1 word]\n",
+                "  literal_block \"for a in [5,4,3,2,1]: print a\" [2 lines]\n",
+                "  block_quote\n",
+                "    paragraph \"It is my business to know things.\" [7 words]\n",
+                "    attribution \"Sherlock Holmes\" [2 words]\n",
+                "  comment \"A comment\" [1 line]\n",
+            )
+        );
+    }
+
+    #[test]
+    fn section_tree() {
+        let section = Section {
+            title: "Installation".to_owned(),
+            children: vec![
+                SectionChildren::Body(paragraph("Text.")),
+                SectionChildren::Transition,
+                SectionChildren::Section(Section {
+                    title: "From source".to_owned(),
+                    children: vec![],
+                }),
+            ],
+        };
+
+        let mut printer = TreePrinter::default();
+        printer.section(&section);
+        assert_eq!(
+            printer.output,
+            concat!(
+                "section \"Installation\"\n",
+                "  paragraph \"Text.\" [1 word]\n",
+                "  transition\n",
+                "  section \"From source\"\n",
+            )
+        );
+    }
+}
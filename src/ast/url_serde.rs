@@ -0,0 +1,14 @@
+//! Serialisation of URLs as strings.
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::Serializer;
+use url::Url;
+
+pub(super) fn serialize<S: Serializer>(url: &Url, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(url.as_str())
+}
+
+pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Url, D::Error> {
+    let url = String::deserialize(deserializer)?;
+    Url::parse(&url).map_err(de::Error::custom)
+}
@@ -0,0 +1,447 @@
+//! Programmatic construction of documents.
+//!
+//! A [`DocumentBuilder`](struct.DocumentBuilder.html) adds blocks to the end of a document.
+//! Sections and lists are built in place by the scoped builders returned when they are added, so
+//! content must be added in document order. Once a section has been added, only further sections
+//! may follow it: anything else would be read back as part of the section.
+//!
+//! ```
+//! use rst::builder::{DocumentBuilder, TextBuilder};
+//!
+//! let mut builder = DocumentBuilder::new();
+//! builder.paragraph("An introduction.");
+//! builder
+//!     .section("Usage")
+//!     .paragraph(TextBuilder::new().text("Run ").literal("make").text(".").build())
+//!     .bullet_list()
+//!     .item("First.")
+//!     .item("Second.");
+//! let document = builder.build();
+//! ```
+//!
+//! Tables are built with the [`TableBuilder`](struct.TableBuilder.html) from the
+//! [`ast`](../ast/index.html) module, which checks that the cells form a grid.
+
+use crate::ast::*;
+
+pub use crate::ast::TableBuilder;
+
+/// Builds a [`Document`](../ast/struct.Document.html).
+///
+/// # Panics
+///
+/// Adding anything other than a section after a section panics.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentBuilder {
+    children: Vec<SectionChildren>,
+}
+
+impl DocumentBuilder {
+    /// Build an empty document.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a section with the given title, returning a builder for its content.
+    pub fn section(&mut self, title: &str) -> SectionBuilder<'_> {
+        self.top_level().into_section(title)
+    }
+
+    /// Add a paragraph.
+    pub fn paragraph(&mut self, text: impl Into<Text>) -> &mut Self {
+        self.top_level().paragraph(text);
+        self
+    }
+
+    /// Add a transition.
+    pub fn transition(&mut self) -> &mut Self {
+        self.top_level().transition();
+        self
+    }
+
+    /// Add a literal block.
+    pub fn literal_block(&mut self, literal: &str) -> &mut Self {
+        self.top_level().literal_block(literal);
+        self
+    }
+
+    /// Add a table.
+    pub fn table(&mut self, table: Table) -> &mut Self {
+        self.top_level().table(table);
+        self
+    }
+
+    /// Add any other block.
+    pub fn block(&mut self, block: BodyBlock) -> &mut Self {
+        self.top_level().block(block);
+        self
+    }
+
+    /// Add a bullet list, returning a builder for its items.
+    pub fn bullet_list(&mut self) -> ListBuilder<'_> {
+        self.top_level().into_list(ListMarker::Bullet)
+    }
+
+    /// Add a list enumerated with arabic numerals from `start`, returning a builder for its items.
+    pub fn enumerated_list(&mut self, start: u64) -> ListBuilder<'_> {
+        self.top_level().into_list(ListMarker::Arabic(start))
+    }
+
+    /// The document built so far.
+    pub fn build(&self) -> Document {
+        Document(self.children.clone())
+    }
+
+    fn top_level(&mut self) -> SectionBuilder<'_> {
+        SectionBuilder {
+            children: &mut self.children,
+        }
+    }
+}
+
+/// Builds the content of a [`Section`](../ast/struct.Section.html) added to a document or to
+/// another section.
+///
+/// # Panics
+///
+/// Adding anything other than a section after a subsection panics.
+#[derive(Debug)]
+pub struct SectionBuilder<'a> {
+    children: &'a mut Vec<SectionChildren>,
+}
+
+impl<'a> SectionBuilder<'a> {
+    /// Add a subsection with the given title, returning a builder for its content.
+    pub fn section(&mut self, title: &str) -> SectionBuilder<'_> {
+        self.reborrow().into_section(title)
+    }
+
+    /// Add a paragraph.
+    pub fn paragraph(&mut self, text: impl Into<Text>) -> &mut Self {
+        self.block(BodyBlock::Paragraph(Paragraph(text.into())))
+    }
+
+    /// Add a transition.
+    pub fn transition(&mut self) -> &mut Self {
+        self.push(SectionChildren::Transition);
+        self
+    }
+
+    /// Add a literal block.
+    pub fn literal_block(&mut self, literal: &str) -> &mut Self {
+        self.block(BodyBlock::LiteralBlock(LiteralBlock(literal.to_owned())))
+    }
+
+    /// Add a table.
+    pub fn table(&mut self, table: Table) -> &mut Self {
+        self.block(BodyBlock::Table(table))
+    }
+
+    /// Add any other block.
+    pub fn block(&mut self, block: BodyBlock) -> &mut Self {
+        self.push(SectionChildren::Body(block));
+        self
+    }
+
+    /// Add a bullet list, returning a builder for its items.
+    pub fn bullet_list(&mut self) -> ListBuilder<'_> {
+        self.reborrow().into_list(ListMarker::Bullet)
+    }
+
+    /// Add a list enumerated with arabic numerals from `start`, returning a builder for its items.
+    pub fn enumerated_list(&mut self, start: u64) -> ListBuilder<'_> {
+        self.reborrow().into_list(ListMarker::Arabic(start))
+    }
+
+    fn reborrow(&mut self) -> SectionBuilder<'_> {
+        SectionBuilder {
+            children: self.children,
+        }
+    }
+
+    /// Add a child that is not a section, which must come before any sections.
+    fn push(&mut self, child: SectionChildren) {
+        assert!(
+            !matches!(self.children.last(), Some(SectionChildren::Section(_))),
+            "only sections may be added after a section"
+        );
+        self.children.push(child);
+    }
+
+    fn into_section(self, title: &str) -> SectionBuilder<'a> {
+        self.children.push(SectionChildren::Section(Section {
+            title: title.to_owned(),
+            children: Vec::new(),
+        }));
+        match self.children.last_mut() {
+            Some(SectionChildren::Section(section)) => SectionBuilder {
+                children: &mut section.children,
+            },
+            _ => unreachable!("a section was just added"),
+        }
+    }
+
+    fn into_list(mut self, marker: ListMarker) -> ListBuilder<'a> {
+        self.push(SectionChildren::Body(BodyBlock::List(List {
+            marker,
+            elements: Vec::new(),
+        })));
+        match self.children.last_mut() {
+            Some(SectionChildren::Body(BodyBlock::List(list))) => ListBuilder { list },
+            _ => unreachable!("a list was just added"),
+        }
+    }
+}
+
+/// Builds the items of a [`List`](../ast/struct.List.html).
+#[derive(Debug)]
+pub struct ListBuilder<'a> {
+    list: &'a mut List,
+}
+
+impl ListBuilder<'_> {
+    /// Add an item holding a single paragraph.
+    pub fn item(&mut self, text: impl Into<Text>) -> &mut Self {
+        self.item_blocks(vec![BodyBlock::Paragraph(Paragraph(text.into()))])
+    }
+
+    /// Add an item holding the given blocks.
+    pub fn item_blocks(&mut self, blocks: Vec<BodyBlock>) -> &mut Self {
+        self.list.elements.push(Body(blocks));
+        self
+    }
+}
+
+/// Builds [`Text`](../ast/struct.Text.html) with inline markup.
+///
+/// Whitespace at either end of plain text separates it from the adjacent markup.
+///
+/// ```
+/// use rst::builder::TextBuilder;
+///
+/// let text = TextBuilder::new().text("Some ").strong("bold").text(" text.").build();
+/// assert_eq!(text.plain_text(), "Some bold text.");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TextBuilder {
+    inlines: Vec<Inline>,
+}
+
+impl TextBuilder {
+    /// Build empty text.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add plain text.
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        if text.starts_with(char::is_whitespace) {
+            self.whitespace();
+        }
+        for (index, word) in text.split_whitespace().enumerate() {
+            if index != 0 {
+                self.whitespace();
+            }
+            self.inlines.push(Inline::Word(word.to_owned()));
+        }
+        if text.ends_with(char::is_whitespace) {
+            self.whitespace();
+        }
+        self
+    }
+
+    /// Add emphasised text.
+    pub fn emphasis(&mut self, text: &str) -> &mut Self {
+        self.inline(Inline::Emphasis(Emphasis(Text::from_plain(text))))
+    }
+
+    /// Add strongly emphasised text.
+    pub fn strong(&mut self, text: &str) -> &mut Self {
+        self.inline(Inline::Strong(Strong(Text::from_plain(text))))
+    }
+
+    /// Add an inline literal, which is kept exactly as given.
+    pub fn literal(&mut self, text: &str) -> &mut Self {
+        self.inline(Inline::Literal(Literal(text.to_owned())))
+    }
+
+    /// Add any other inline item.
+    pub fn inline(&mut self, inline: Inline) -> &mut Self {
+        self.inlines.push(inline);
+        self
+    }
+
+    /// The text built so far, without trailing whitespace.
+    pub fn build(&self) -> Text {
+        let mut inlines = self.inlines.clone();
+        if inlines.last() == Some(&Inline::Whitespace) {
+            inlines.pop();
+        }
+        Text(inlines)
+    }
+
+    fn whitespace(&mut self) {
+        if !self.inlines.is_empty() && self.inlines.last() != Some(&Inline::Whitespace) {
+            self.inlines.push(Inline::Whitespace);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::write::{HtmlWriter, RstWriter};
+
+    fn rst(document: &Document) -> String {
+        let mut output = Vec::new();
+        RstWriter::default()
+            .write_document(document, &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn build_document() {
+        let mut builder = DocumentBuilder::new();
+        builder.paragraph("An introduction.").transition();
+        builder.paragraph("More.");
+        {
+            let mut section = builder.section("Usage");
+            section
+                .paragraph("Run the tool:")
+                .literal_block("tool --help");
+            section.enumerated_list(3).item("Three.").item("Four.");
+        }
+        builder.section("Empty");
+        let document = builder.build();
+
+        let text = concat!(
+            "An introduction.\n",
+            "\n",
+            "----------\n",
+            "\n",
+            "More.\n",
+            "\n",
+            "Usage\n",
+            "=====\n",
+            "\n",
+            "Run the tool::\n",
+            "\n",
+            "    tool --help\n",
+            "\n",
+            "3. Three.\n",
+            "4. Four.\n",
+            "\n",
+            "Empty\n",
+            "=====\n",
+        );
+        let parsed: Document = text.parse().unwrap();
+        assert_eq!(document.tree_string(), parsed.tree_string());
+    }
+
+    #[test]
+    fn nested_builders() {
+        let mut builder = DocumentBuilder::new();
+        builder
+            .section("Outer")
+            .paragraph("Text.")
+            .section("Inner")
+            .bullet_list()
+            .item("Item.")
+            .item_blocks(vec![]);
+        let document = builder.build();
+
+        assert_eq!(
+            document.tree_string(),
+            concat!(
+                "document\n",
+                "  section \"Outer\"\n",
+                "    paragraph \"Text.\" [1 word]\n",
+                "    section \"Inner\"\n",
+                "      bullet_list\n",
+                "        list_item\n",
+                "          paragraph \"Item.\" [1 word]\n",
+                "        list_item\n",
+            )
+        );
+    }
+
+    #[test]
+    fn round_trip_built_document() {
+        let mut builder = DocumentBuilder::new();
+        builder.paragraph("Before the sections.").transition();
+        builder.paragraph("After the transition.");
+        {
+            let mut section = builder.section("One");
+            section.bullet_list().item("Item.");
+            section
+                .section("Inner")
+                .literal_block("code")
+                .paragraph("Text.");
+            section.section("Second inner");
+        }
+        builder.section("Two").paragraph("Last.");
+        let document = builder.build();
+
+        let parsed: Document = rst(&document).parse().unwrap();
+        assert_eq!(parsed.tree_string(), document.tree_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "only sections may be added after a section")]
+    fn block_after_section() {
+        let mut builder = DocumentBuilder::new();
+        builder.section("Title");
+        builder.paragraph("Read back as part of the section.");
+    }
+
+    #[test]
+    #[should_panic(expected = "only sections may be added after a section")]
+    fn transition_after_subsection() {
+        let mut builder = DocumentBuilder::new();
+        let mut section = builder.section("Title");
+        section.section("Subsection");
+        section.transition();
+    }
+
+    #[test]
+    fn build_text() {
+        let text = TextBuilder::new()
+            .text(" Leading ")
+            .emphasis("emphasis")
+            .text(", ")
+            .strong("strong")
+            .text("and ")
+            .literal("a  b")
+            .text("  ")
+            .build();
+        assert_eq!(text.plain_text(), "Leading emphasis, strongand a  b");
+
+        let mut builder = DocumentBuilder::new();
+        builder.paragraph(text);
+        let mut html = Vec::new();
+        HtmlWriter::default()
+            .write_document(&builder.build(), &mut html)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(html).unwrap(),
+            concat!(
+                "<p>Leading <em>emphasis</em>, <strong>strong</strong>and ",
+                "<code>a  b</code></p>\n",
+            )
+        );
+    }
+
+    #[test]
+    fn write_built_table() {
+        let table = TableBuilder::new(2)
+            .row()
+            .cell("a")
+            .cell("b")
+            .build()
+            .unwrap();
+        let mut builder = DocumentBuilder::new();
+        builder.table(table);
+        assert_eq!(rst(&builder.build()), "+---+---+\n| a | b |\n+---+---+\n");
+    }
+}
@@ -0,0 +1,548 @@
+//! Conformance of the parser to the reStructuredText specification.
+//!
+//! Each [feature](struct.Feature.html) of the specification has a fixture: a short document
+//! using the feature, with the HTML that docutils would produce for it, as written by
+//! [`HtmlWriter`](../write/struct.HtmlWriter.html). [`run`](fn.run.html) parses each fixture and
+//! compares the result to produce a [`Report`](struct.Report.html).
+//!
+//! Print the report with:
+//!
+//! ```text
+//! cargo run --example conformance --features conformance [-- --json]
+//! ```
+
+use std::fmt::Write;
+
+use crate::ast::Document;
+use crate::write::HtmlWriter;
+use crate::ParseErrorKind;
+
+/// The address of the specification, to which the anchor of each feature is appended.
+const SPECIFICATION: &str = "http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html";
+
+/// A feature of the reStructuredText specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Feature {
+    /// A short, stable identifier for the feature.
+    pub id: &'static str,
+    pub description: &'static str,
+    /// The anchor of the section of the specification describing the feature.
+    pub anchor: &'static str,
+    /// A document using the feature.
+    pub fixture: &'static str,
+    /// The HTML written for the fixture.
+    pub expected: &'static str,
+}
+
+impl Feature {
+    /// The address of the section of the specification describing the feature.
+    pub fn spec_url(&self) -> String {
+        format!("{}#{}", SPECIFICATION, self.anchor)
+    }
+
+    /// Parse the fixture and compare the result to the expected HTML.
+    pub fn check(&self) -> Outcome {
+        let document: Document = match self.fixture.parse() {
+            Ok(document) => document,
+            Err(error) => {
+                return match error.kind() {
+                    ParseErrorKind::Unsupported { .. } => Outcome::NotImplemented,
+                    _ => Outcome::Fail(error.to_string()),
+                }
+            }
+        };
+
+        let mut html = Vec::new();
+        if let Err(error) = HtmlWriter::default().write_document(&document, &mut html) {
+            return Outcome::Fail(error.to_string());
+        }
+        match String::from_utf8(html) {
+            Ok(html) if html == self.expected => Outcome::Pass,
+            Ok(html) => Outcome::Fail(format!("wrote:\n{}", html)),
+            Err(error) => Outcome::Fail(error.to_string()),
+        }
+    }
+}
+
+/// The result of checking a [`Feature`](struct.Feature.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Pass,
+    /// The fixture was parsed or written incorrectly, for the given reason.
+    Fail(String),
+    /// The parser reports that the feature is not supported.
+    NotImplemented,
+}
+
+impl Outcome {
+    fn name(&self) -> &'static str {
+        match self {
+            Outcome::Pass => "pass",
+            Outcome::Fail(_) => "fail",
+            Outcome::NotImplemented => "not implemented",
+        }
+    }
+}
+
+/// The outcome of checking each feature.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub results: Vec<(Feature, Outcome)>,
+}
+
+impl Report {
+    /// The number of features with the given outcome.
+    pub fn count(&self, outcome: impl Fn(&Outcome) -> bool) -> usize {
+        self.results.iter().filter(|(_, o)| outcome(o)).count()
+    }
+
+    /// Render the report as a Markdown table.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("# reStructuredText conformance\n\n");
+        writeln!(
+            markdown,
+            "{} of {} features pass, {} fail and {} are not implemented.\n",
+            self.count(|o| *o == Outcome::Pass),
+            self.results.len(),
+            self.count(|o| matches!(o, Outcome::Fail(_))),
+            self.count(|o| *o == Outcome::NotImplemented),
+        )
+        .expect("writing to a string cannot fail");
+
+        markdown.push_str("| Feature | Description | Result |\n");
+        markdown.push_str("| --- | --- | --- |\n");
+        for (feature, outcome) in &self.results {
+            writeln!(
+                markdown,
+                "| [`{}`]({}) | {} | {} |",
+                feature.id,
+                feature.spec_url(),
+                feature.description,
+                outcome.name()
+            )
+            .expect("writing to a string cannot fail");
+        }
+        markdown
+    }
+
+    /// Render the report as JSON.
+    ///
+    /// The report is an object with a `features` array, holding an object for each feature with
+    /// its `id`, `description`, `spec` URL, and `result`: one of `pass`, `fail` or
+    /// `not implemented`. Failures also have a `reason`.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\"features\":[");
+        for (index, (feature, outcome)) in self.results.iter().enumerate() {
+            if index != 0 {
+                json.push(',');
+            }
+            write!(
+                json,
+                "{{\"id\":{},\"description\":{},\"spec\":{},\"result\":{}",
+                json_string(feature.id),
+                json_string(feature.description),
+                json_string(&feature.spec_url()),
+                json_string(outcome.name())
+            )
+            .expect("writing to a string cannot fail");
+            if let Outcome::Fail(reason) = outcome {
+                write!(json, ",\"reason\":{}", json_string(reason))
+                    .expect("writing to a string cannot fail");
+            }
+            json.push('}');
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+/// Quote a string for JSON.
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => {
+                write!(quoted, "\\u{:04x}", c as u32).expect("writing to a string cannot fail")
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Check every feature.
+pub fn run() -> Report {
+    Report {
+        results: features()
+            .iter()
+            .map(|feature| (*feature, feature.check()))
+            .collect(),
+    }
+}
+
+/// The features of the specification, in the order they are described.
+pub fn features() -> &'static [Feature] {
+    FEATURES
+}
+
+const FEATURES: &[Feature] = &[
+    Feature {
+        id: "paragraphs",
+        description: "Paragraphs separated by blank lines",
+        anchor: "paragraphs",
+        fixture: "First paragraph,\non two lines.\n\nSecond paragraph.\n",
+        expected: "<p>First paragraph, on two lines.</p>\n<p>Second paragraph.</p>\n",
+    },
+    Feature {
+        id: "sections",
+        description: "Section titles with underlines",
+        anchor: "sections",
+        fixture: "Title\n=====\n\nText.\n\nSubtitle\n--------\n",
+        expected: concat!(
+            "<section id=\"title\">\n<h1>Title</h1>\n<p>Text.</p>\n",
+            "<section id=\"subtitle\">\n<h2>Subtitle</h2>\n</section>\n</section>\n",
+        ),
+    },
+    Feature {
+        id: "section-overlines",
+        description: "Section titles with overlines and underlines",
+        anchor: "sections",
+        fixture: "=======\n Title\n=======\n\nText.\n",
+        expected: "<section id=\"title\">\n<h1>Title</h1>\n<p>Text.</p>\n</section>\n",
+    },
+    Feature {
+        id: "transitions",
+        description: "Transitions between paragraphs",
+        anchor: "transitions",
+        fixture: "Before.\n\n----------\n\nAfter.\n",
+        expected: "<p>Before.</p>\n<hr class=\"docutils\" />\n<p>After.</p>\n",
+    },
+    Feature {
+        id: "bullet-lists",
+        description: "Bullet lists",
+        anchor: "bullet-lists",
+        fixture: "- One.\n- Two.\n",
+        expected: "<ul>\n<li>\n<p>One.</p>\n</li>\n<li>\n<p>Two.</p>\n</li>\n</ul>\n",
+    },
+    Feature {
+        id: "nested-lists",
+        description: "Lists nested within list items",
+        anchor: "bullet-lists",
+        fixture: "- One.\n\n  * Nested.\n",
+        expected:
+            "<ul>\n<li>\n<p>One.</p>\n<ul>\n<li>\n<p>Nested.</p>\n</li>\n</ul>\n</li>\n</ul>\n",
+    },
+    Feature {
+        id: "enumerated-lists",
+        description: "Lists enumerated with numbers, letters and roman numerals",
+        anchor: "enumerated-lists",
+        fixture: "3. Three.\n4. Four.\n\n(a) Letter.\n",
+        expected: concat!(
+            "<ol class=\"arabic\" start=\"3\">\n<li>\n<p>Three.</p>\n</li>\n<li>\n<p>Four.</p>\n",
+            "</li>\n</ol>\n<ol class=\"loweralpha\">\n<li>\n<p>Letter.</p>\n</li>\n</ol>\n",
+        ),
+    },
+    Feature {
+        id: "auto-enumerated-lists",
+        description: "Lists enumerated automatically with `#`",
+        anchor: "enumerated-lists",
+        fixture: "#. One.\n#. Two.\n",
+        expected:
+            "<ol class=\"arabic\">\n<li>\n<p>One.</p>\n</li>\n<li>\n<p>Two.</p>\n</li>\n</ol>\n",
+    },
+    Feature {
+        id: "definition-lists",
+        description: "Definition lists with classifiers",
+        anchor: "definition-lists",
+        fixture: "term : classifier\n    Definition.\n",
+        expected: concat!(
+            "<dl>\n<dt>term<span class=\"classifier-delimiter\">:</span> ",
+            "<span class=\"classifier\">classifier</span></dt>\n<dd>\n<p>Definition.</p>\n",
+            "</dd>\n</dl>\n",
+        ),
+    },
+    Feature {
+        id: "field-lists",
+        description: "Field lists",
+        anchor: "field-lists",
+        fixture: ":Author: Me\n:Version: 1\n",
+        expected: concat!(
+            "<dl class=\"field-list\">\n<dt>Author</dt>\n<dd>\n<p>Me</p>\n</dd>\n",
+            "<dt>Version</dt>\n<dd>\n<p>1</p>\n</dd>\n</dl>\n",
+        ),
+    },
+    Feature {
+        id: "option-lists",
+        description: "Option lists of command-line options",
+        anchor: "option-lists",
+        fixture: "-a         All.\n--file=F   A file.\n",
+        expected: concat!(
+            "<dl class=\"option-list\">\n<dt><kbd><span class=\"option\">-a</span></kbd></dt>\n",
+            "<dd><p>All.</p></dd>\n",
+            "<dt><kbd><span class=\"option\">--file <var>F</var></span></kbd></dt>\n",
+            "<dd><p>A file.</p></dd>\n</dl>\n",
+        ),
+    },
+    Feature {
+        id: "literal-blocks",
+        description: "Indented literal blocks introduced by `::`",
+        anchor: "literal-blocks",
+        fixture: "Code::\n\n    if a < b:\n        pass\n",
+        expected: "<p>Code:</p>\n<pre><code>if a &lt; b:\n    pass</code></pre>\n",
+    },
+    Feature {
+        id: "quoted-literal-blocks",
+        description: "Unindented literal blocks quoted with punctuation",
+        anchor: "quoted-literal-blocks",
+        fixture: "Mail::\n\n> Quoted.\n> Again.\n",
+        expected: "<p>Mail:</p>\n<pre><code>&gt; Quoted.\n&gt; Again.</code></pre>\n",
+    },
+    Feature {
+        id: "line-blocks",
+        description: "Line blocks preserving line breaks",
+        anchor: "line-blocks",
+        fixture: "| First line\n| Second line\n",
+        expected: concat!(
+            "<div class=\"line-block\">\n<div class=\"line\">First line</div>\n",
+            "<div class=\"line\">Second line</div>\n</div>\n",
+        ),
+    },
+    Feature {
+        id: "block-quotes",
+        description: "Indented block quotes",
+        anchor: "block-quotes",
+        fixture: "Intro.\n\n    Quoted.\n",
+        expected: "<p>Intro.</p>\n<blockquote>\n<p>Quoted.</p>\n</blockquote>\n",
+    },
+    Feature {
+        id: "attributions",
+        description: "Attributions ending block quotes",
+        anchor: "block-quotes",
+        fixture: "Intro.\n\n    Quoted.\n\n    -- Author\n",
+        expected: concat!(
+            "<p>Intro.</p>\n<blockquote>\n<p>Quoted.</p>\n",
+            "<p class=\"attribution\">—Author</p>\n</blockquote>\n",
+        ),
+    },
+    Feature {
+        id: "doctest-blocks",
+        description: "Interactive Python sessions",
+        anchor: "doctest-blocks",
+        fixture: ">>> print('hi')\nhi\n",
+        expected: "<pre class=\"code python doctest\">&gt;&gt;&gt; print('hi')\nhi</pre>\n",
+    },
+    Feature {
+        id: "grid-tables",
+        description: "Grid tables",
+        anchor: "grid-tables",
+        fixture: "+---+---+\n| a | b |\n+---+---+\n",
+        expected: "<table>\n<tbody>\n<tr>\n<td>a</td>\n<td>b</td>\n</tr>\n</tbody>\n</table>\n",
+    },
+    Feature {
+        id: "simple-tables",
+        description: "Simple tables",
+        anchor: "simple-tables",
+        fixture: "=  =\na  b\n=  =\n",
+        expected: "<table>\n<tbody>\n<tr>\n<td>a</td>\n<td>b</td>\n</tr>\n</tbody>\n</table>\n",
+    },
+    Feature {
+        id: "numbered-footnotes",
+        description: "Manually numbered footnotes",
+        anchor: "footnotes",
+        fixture: ".. [1] A footnote.\n",
+        expected: concat!(
+            "<aside class=\"footnote\" id=\"id1\">\n<span class=\"label\">[1]</span>\n",
+            "<p>A footnote.</p>\n</aside>\n",
+        ),
+    },
+    Feature {
+        id: "auto-numbered-footnotes",
+        description: "Automatically numbered footnotes, with and without labels",
+        anchor: "auto-numbered-footnotes",
+        fixture: ".. [#] First.\n.. [#note] Second.\n",
+        expected: concat!(
+            "<aside class=\"footnote\" id=\"id1\">\n<span class=\"label\">[1]</span>\n",
+            "<p>First.</p>\n</aside>\n",
+            "<aside class=\"footnote\" id=\"note\">\n<span class=\"label\">[2]</span>\n",
+            "<p>Second.</p>\n</aside>\n",
+        ),
+    },
+    Feature {
+        id: "auto-symbol-footnotes",
+        description: "Footnotes labelled with symbols",
+        anchor: "auto-symbol-footnotes",
+        fixture: ".. [*] First.\n.. [*] Second.\n",
+        expected: concat!(
+            "<aside class=\"footnote\" id=\"id1\">\n<span class=\"label\">[*]</span>\n",
+            "<p>First.</p>\n</aside>\n",
+            "<aside class=\"footnote\" id=\"id2\">\n<span class=\"label\">[†]</span>\n",
+            "<p>Second.</p>\n</aside>\n",
+        ),
+    },
+    Feature {
+        id: "citations",
+        description: "Citations",
+        anchor: "citations",
+        fixture: ".. [CIT2002] A citation.\n",
+        expected: concat!(
+            "<div class=\"citation\" id=\"cit2002\">\n<span class=\"label\">[CIT2002]</span>\n",
+            "<p>A citation.</p>\n</div>\n",
+        ),
+    },
+    Feature {
+        id: "hyperlink-targets",
+        description: "External hyperlink targets",
+        anchor: "hyperlink-targets",
+        fixture: ".. _Python: https://www.python.org/\n",
+        expected: "",
+    },
+    Feature {
+        id: "directives",
+        description: "Directives",
+        anchor: "directives",
+        fixture: ".. note:: Read this.\n",
+        expected: "<div class=\"note\">\n<p>Read this.</p>\n</div>\n",
+    },
+    Feature {
+        id: "substitution-definitions",
+        description: "Substitution definitions",
+        anchor: "substitution-definitions",
+        fixture: ".. |name| replace:: text\n",
+        expected: "",
+    },
+    Feature {
+        id: "comments",
+        description: "Comments, including those spanning several lines",
+        anchor: "comments",
+        fixture: ".. A comment\n   on two lines.\n",
+        expected: "<!-- A comment\non two lines. -->\n",
+    },
+    Feature {
+        id: "emphasis",
+        description: "Emphasised text",
+        anchor: "emphasis",
+        fixture: "Some *emphasis*.\n",
+        expected: "<p>Some <em>emphasis</em>.</p>\n",
+    },
+    Feature {
+        id: "strong-emphasis",
+        description: "Strongly emphasised text",
+        anchor: "strong-emphasis",
+        fixture: "Some **strong** text.\n",
+        expected: "<p>Some <strong>strong</strong> text.</p>\n",
+    },
+    Feature {
+        id: "inline-literals",
+        description: "Inline literals",
+        anchor: "inline-literals",
+        fixture: "Run ``make``.\n",
+        expected: "<p>Run <code>make</code>.</p>\n",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conformance_report() {
+        let report = run();
+        let markdown = report.to_markdown();
+        assert!(markdown.starts_with("# reStructuredText conformance\n"));
+        // A header, a separator and a row for each feature.
+        assert_eq!(
+            markdown
+                .lines()
+                .filter(|line| line.starts_with('|'))
+                .count(),
+            report.results.len() + 2
+        );
+
+        assert_eq!(features().len(), 30);
+        for (index, feature) in features().iter().enumerate() {
+            assert!(
+                features()[..index]
+                    .iter()
+                    .all(|other| other.id != feature.id),
+                "duplicate feature {}",
+                feature.id
+            );
+        }
+
+        // Features that are implemented must not regress.
+        let passing = [
+            "paragraphs",
+            "sections",
+            "section-overlines",
+            "transitions",
+            "bullet-lists",
+            "nested-lists",
+            "enumerated-lists",
+            "auto-enumerated-lists",
+            "literal-blocks",
+            "quoted-literal-blocks",
+            "block-quotes",
+            "simple-tables",
+            "attributions",
+            "numbered-footnotes",
+            "auto-numbered-footnotes",
+            "auto-symbol-footnotes",
+            "citations",
+            "comments",
+        ];
+        for (feature, outcome) in &report.results {
+            if passing.contains(&feature.id) {
+                assert_eq!(outcome, &Outcome::Pass, "{}", feature.id);
+            }
+        }
+    }
+
+    #[test]
+    fn report_formats() {
+        let feature = |id| Feature {
+            id,
+            description: "A \"quoted\" description",
+            anchor: "anchor",
+            fixture: "",
+            expected: "",
+        };
+        let report = Report {
+            results: vec![
+                (feature("a"), Outcome::Pass),
+                (feature("b"), Outcome::Fail("wrote:\n<p>".to_owned())),
+                (feature("c"), Outcome::NotImplemented),
+            ],
+        };
+
+        assert_eq!(
+            report.to_markdown(),
+            concat!(
+                "# reStructuredText conformance\n",
+                "\n",
+                "1 of 3 features pass, 1 fail and 1 are not implemented.\n",
+                "\n",
+                "| Feature | Description | Result |\n",
+                "| --- | --- | --- |\n",
+                "| [`a`](http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#anchor)",
+                " | A \"quoted\" description | pass |\n",
+                "| [`b`](http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#anchor)",
+                " | A \"quoted\" description | fail |\n",
+                "| [`c`](http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#anchor)",
+                " | A \"quoted\" description | not implemented |\n",
+            )
+        );
+
+        let json = report.to_json();
+        assert!(json.starts_with(concat!(
+            "{\"features\":[{\"id\":\"a\",\"description\":\"A \\\"quoted\\\" description\",",
+            "\"spec\":\"http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html#anchor\",",
+            "\"result\":\"pass\"},",
+        )));
+        assert!(json.contains("\"result\":\"fail\",\"reason\":\"wrote:\\n<p>\"}"));
+        assert!(json.ends_with("\"result\":\"not implemented\"}]}"));
+    }
+}
@@ -0,0 +1,199 @@
+//! Errors produced while parsing a document.
+
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::location::{Location, Locator, Span};
+use crate::tokens::{Token, TokenError, TokenErrorKind};
+
+/// An error encountered while parsing a document.
+///
+/// The span covers the text at fault. For errors reading the source, the span is empty and begins
+/// where reading stopped.
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct ParseError(Box<Inner>);
+
+// Boxed to keep results containing errors small.
+#[derive(Debug)]
+struct Inner {
+    kind: ParseErrorKind,
+    span: Span,
+    source_name: String,
+}
+
+impl fmt::Display for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {}",
+            self.source_name,
+            self.span.start(),
+            self.kind
+        )
+    }
+}
+
+// The message already includes that of the kind, so the chain continues with its cause.
+impl std::error::Error for Inner {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.kind.source()
+    }
+}
+
+impl ParseError {
+    /// A token was found where something else was expected in the named source.
+    pub fn unexpected_token(
+        token: &Token,
+        span: Span,
+        expected: &str,
+        source_name: &str,
+    ) -> ParseError {
+        ParseError::new(
+            ParseErrorKind::UnexpectedToken {
+                expected: expected.to_owned(),
+                found: describe(token),
+            },
+            span,
+        )
+        .in_source(source_name)
+    }
+
+    /// The named source ended before the construct being parsed was complete.
+    pub fn unexpected_eof(location: Location, source_name: &str) -> ParseError {
+        ParseError::new(ParseErrorKind::UnexpectedEof, location.span_to(&location))
+            .in_source(source_name)
+    }
+
+    pub(crate) fn unsupported(construct: &'static str, span: Span) -> ParseError {
+        ParseError::new(ParseErrorKind::Unsupported { construct }, span)
+    }
+
+    pub(crate) fn mismatched_overline(span: Span) -> ParseError {
+        ParseError::new(ParseErrorKind::MismatchedOverline, span)
+    }
+
+    pub(crate) fn inconsistent_title_level(span: Span) -> ParseError {
+        ParseError::new(ParseErrorKind::InconsistentTitleLevel, span)
+    }
+
+    pub(crate) fn malformed_table(problem: &'static str, span: Span) -> ParseError {
+        ParseError::new(ParseErrorKind::MalformedTable { problem }, span)
+    }
+
+    fn new(kind: ParseErrorKind, span: Span) -> ParseError {
+        ParseError(Box::new(Inner {
+            kind,
+            span,
+            source_name: String::new(),
+        }))
+    }
+
+    /// Attribute the error to a named source.
+    pub(crate) fn in_source(mut self, name: &str) -> ParseError {
+        self.0.source_name = name.to_owned();
+        self
+    }
+
+    /// The kind of error.
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.0.kind
+    }
+
+    /// The span of the text at fault.
+    pub fn span(&self) -> &Span {
+        &self.0.span
+    }
+
+    /// The name of the source in which the error occurred.
+    pub fn source_name(&self) -> &str {
+        &self.0.source_name
+    }
+}
+
+impl From<TokenError> for ParseError {
+    fn from(error: TokenError) -> Self {
+        ParseError(Box::new(Inner {
+            kind: ParseErrorKind::Token(error.kind),
+            span: error.location.span_to(&error.location),
+            source_name: error.source_name,
+        }))
+    }
+}
+
+/// The kinds of [`ParseError`](struct.ParseError.html).
+#[derive(Debug, Error)]
+pub enum ParseErrorKind {
+    /// The source could not be tokenised.
+    #[error(transparent)]
+    Token(TokenErrorKind),
+    /// A token was found where something else was expected.
+    #[error("expected {expected}, found {found}")]
+    UnexpectedToken { expected: String, found: String },
+    /// The source ended before the construct being parsed was complete.
+    #[error("unexpected end of input")]
+    UnexpectedEof,
+    /// The document uses a construct that the parser does not yet recognise.
+    #[error("{construct} is not supported")]
+    Unsupported { construct: &'static str },
+    /// The overline of a section title differs from its underline in character or length.
+    #[error("section title overline does not match its underline")]
+    MismatchedOverline,
+    /// A section title is more than one level deeper than the section containing it.
+    #[error("section title level is inconsistent")]
+    InconsistentTitleLevel,
+    /// The lines of a table do not line up with its borders.
+    #[error("malformed table: {problem}")]
+    MalformedTable { problem: &'static str },
+}
+
+/// Describe a token for an error message.
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Newline => "end of line".to_owned(),
+        Token::Whitespace(_) => "whitespace".to_owned(),
+        Token::Word(word) => format!("{:?}", word),
+        token => format!("{:?}", token.to_char().unwrap_or_default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::TextSource;
+    use crate::TokenStream;
+    use std::error::Error as _;
+
+    #[test]
+    fn messages() {
+        let mut source = TextSource::from_str("index.rst", "a\n:: word");
+        let tokens: Vec<_> = TokenStream::try_new(&mut source)
+            .unwrap()
+            .map(|item| {
+                let (token, span) = item.unwrap();
+                (token, *span)
+            })
+            .collect();
+
+        let (colon, span) = &tokens[2];
+        let error = ParseError::unexpected_token(colon, *span, "a field name", "index.rst");
+        assert_eq!(
+            error.to_string(),
+            "index.rst[1:0]: expected a field name, found ':'"
+        );
+        assert_eq!(error.span().len(), 1);
+
+        let (word, span) = &tokens[5];
+        let error = ParseError::unexpected_token(word, *span, "a colon", "index.rst");
+        assert_eq!(
+            error.to_string(),
+            "index.rst[1:3]: expected a colon, found \"word\""
+        );
+
+        let error = ParseError::unexpected_eof(*span.end(), "index.rst");
+        assert_eq!(error.to_string(), "index.rst[1:7]: unexpected end of input");
+        assert!(error.span().is_empty());
+        assert!(error.source().is_none());
+    }
+}
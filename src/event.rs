@@ -0,0 +1,357 @@
+//! Pull-based traversal of a document as a sequence of events.
+//!
+//! An [`EventStream`](struct.EventStream.html) produces an [`RstEvent`](enum.RstEvent.html) for
+//! the start and end of each node, with the inline items of text between them. This suits
+//! converters that write their output as they go.
+//!
+//! ```
+//! use rst::event::{EventStream, RstEvent};
+//! use rst::location::TextSource;
+//! use rst::TokenStream;
+//!
+//! let mut source = TextSource::from_str("example", "A paragraph.\n");
+//! let events = EventStream::new(TokenStream::try_new(&mut source)?);
+//! let paragraphs = events
+//!     .filter(|event| matches!(event, Ok(RstEvent::StartParagraph)))
+//!     .count();
+//! assert_eq!(paragraphs, 1);
+//! # Ok::<(), rst::ParseError>(())
+//! ```
+//!
+//! The parser does not yet produce events incrementally: the whole document is parsed when the
+//! first event is requested. The events are then read from the syntax tree one at a time as they
+//! are requested, without collecting them first.
+
+use std::vec;
+
+use crate::ast::*;
+use crate::location::Source;
+use crate::{ParseError, Parser, TokenStream};
+
+/// An event in the traversal of a document.
+///
+/// Each `Start` event is matched by the corresponding `End` event once the content of the node
+/// has been produced. Blocks without children are produced as a single event.
+///
+/// Events for more kinds of block are added as the parser supports them, so this enum is
+/// `non_exhaustive`; blocks without their own events are produced whole as a
+/// [`Block`](#variant.Block).
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RstEvent {
+    StartDocument,
+    EndDocument,
+    /// The start of a section, where the sections of the document are at level 1.
+    StartSection {
+        level: u32,
+        title: Text,
+    },
+    EndSection,
+    Transition,
+    StartParagraph,
+    EndParagraph,
+    /// An inline item of the text of a paragraph or attribution.
+    Inline(Inline),
+    StartList {
+        marker: ListMarker,
+    },
+    EndList,
+    StartListItem,
+    EndListItem,
+    StartBlockQuote,
+    EndBlockQuote,
+    StartAttribution,
+    EndAttribution,
+    LiteralBlock(String),
+    DocTest(String),
+    Comment(String),
+    /// Any other block, with its content.
+    Block(BodyBlock),
+}
+
+/// Produces the events of a document parsed from a stream of tokens.
+///
+/// If the document cannot be parsed, the error is produced in place of the first event and the
+/// stream then ends.
+pub struct EventStream<'s, S: Source> {
+    tokens: Option<TokenStream<'s, S>>,
+    events: Option<Events>,
+}
+
+impl<'s, S: Source + 's> EventStream<'s, S> {
+    pub fn new(tokens: TokenStream<'s, S>) -> Self {
+        EventStream {
+            tokens: Some(tokens),
+            events: None,
+        }
+    }
+}
+
+impl<'s, S: Source + 's> Iterator for EventStream<'s, S> {
+    type Item = Result<RstEvent, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(tokens) = self.tokens.take() {
+            match Parser::new(tokens).parse() {
+                Ok(document) => self.events = Some(Events::new(document)),
+                Err(error) => return Some(Err(error)),
+            }
+        }
+        self.events.as_mut()?.next().map(Ok)
+    }
+}
+
+/// The events of a syntax tree, read from it one at a time.
+struct Events {
+    /// The nodes still to be visited, innermost last.
+    stack: Vec<Frame>,
+    started: bool,
+}
+
+impl Events {
+    fn new(Document(children): Document) -> Self {
+        let mut events = Events {
+            stack: Vec::new(),
+            started: false,
+        };
+        events
+            .push(Nodes::Children(children.into_iter(), 0))
+            .ending(RstEvent::EndDocument);
+        events
+    }
+
+    /// The next event of the node at the top of the stack, or of its parent once it is done.
+    fn next_event(&mut self) -> Option<RstEvent> {
+        loop {
+            let frame = self.stack.last_mut()?;
+            let event = match &mut frame.nodes {
+                Nodes::Children(children, level) => {
+                    let level = *level;
+                    children.next().map(|child| match child {
+                        SectionChildren::Body(block) => self.block(block),
+                        SectionChildren::Transition => RstEvent::Transition,
+                        SectionChildren::Section(Section { title, children }) => {
+                            self.push(Nodes::Children(children.into_iter(), level + 1))
+                                .ending(RstEvent::EndSection);
+                            RstEvent::StartSection {
+                                level: level + 1,
+                                title: Text::from_plain(&title),
+                            }
+                        }
+                    })
+                }
+                Nodes::Blocks(blocks) => blocks.next().map(|block| self.block(block)),
+                Nodes::Items(items) => items.next().map(|Body(blocks)| {
+                    self.push(Nodes::Blocks(blocks.into_iter()))
+                        .ending(RstEvent::EndListItem);
+                    RstEvent::StartListItem
+                }),
+                Nodes::Attribution(attribution) => attribution.take().map(|Text(inlines)| {
+                    self.push(Nodes::Inlines(inlines.into_iter()))
+                        .ending(RstEvent::EndAttribution);
+                    RstEvent::StartAttribution
+                }),
+                Nodes::Inlines(inlines) => inlines.next().map(RstEvent::Inline),
+            };
+
+            match event {
+                Some(event) => return Some(event),
+                None => {
+                    let frame = self.stack.pop().expect("the frame is on the stack");
+                    if let Some(end) = frame.end {
+                        return Some(end);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The first event of a block, leaving the rest of it on the stack.
+    fn block(&mut self, block: BodyBlock) -> RstEvent {
+        match block {
+            BodyBlock::Paragraph(Paragraph(Text(inlines))) => {
+                self.push(Nodes::Inlines(inlines.into_iter()))
+                    .ending(RstEvent::EndParagraph);
+                RstEvent::StartParagraph
+            }
+            BodyBlock::List(List { marker, elements }) => {
+                self.push(Nodes::Items(elements.into_iter()))
+                    .ending(RstEvent::EndList);
+                RstEvent::StartList { marker }
+            }
+            BodyBlock::BlockQuote(BlockQuote {
+                quote: Body(blocks),
+                attribution,
+            }) => {
+                self.push(Nodes::Attribution(attribution))
+                    .ending(RstEvent::EndBlockQuote);
+                self.push(Nodes::Blocks(blocks.into_iter()));
+                RstEvent::StartBlockQuote
+            }
+            BodyBlock::LiteralBlock(LiteralBlock(literal)) => RstEvent::LiteralBlock(literal),
+            BodyBlock::DocTest(DocTest(doctest)) => RstEvent::DocTest(doctest),
+            BodyBlock::Comment(Comment(comment)) => RstEvent::Comment(comment),
+            block => RstEvent::Block(block),
+        }
+    }
+
+    fn push(&mut self, nodes: Nodes) -> &mut Frame {
+        self.stack.push(Frame { nodes, end: None });
+        self.stack.last_mut().expect("a frame was just pushed")
+    }
+}
+
+impl Iterator for Events {
+    type Item = RstEvent;
+
+    fn next(&mut self) -> Option<RstEvent> {
+        if !self.started {
+            self.started = true;
+            return Some(RstEvent::StartDocument);
+        }
+        self.next_event()
+    }
+}
+
+/// The remaining children of a node, with the event that ends the node.
+struct Frame {
+    nodes: Nodes,
+    end: Option<RstEvent>,
+}
+
+impl Frame {
+    fn ending(&mut self, end: RstEvent) {
+        self.end = Some(end);
+    }
+}
+
+enum Nodes {
+    /// The children of the document or of a section at the given level.
+    Children(vec::IntoIter<SectionChildren>, u32),
+    Blocks(vec::IntoIter<BodyBlock>),
+    Items(vec::IntoIter<Body>),
+    Attribution(Option<Text>),
+    Inlines(vec::IntoIter<Inline>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::TextSource;
+    use crate::ParseErrorKind;
+
+    fn events(text: &str) -> Vec<Result<RstEvent, ParseError>> {
+        let mut source = TextSource::from_str("test", text);
+        EventStream::new(TokenStream::try_new(&mut source).unwrap()).collect()
+    }
+
+    #[test]
+    fn document_events() {
+        let text = concat!(
+            "Title\n",
+            "=====\n",
+            "\n",
+            "- Item\n",
+            "\n",
+            "Between\n",
+            "\n",
+            "    Quoted.\n",
+            "\n",
+            "    -- Author\n",
+            "\n",
+            "Code::\n",
+            "\n",
+            "    code\n",
+            "\n",
+            ".. [1] Note.\n",
+        );
+        let word = |word: &str| RstEvent::Inline(Inline::Word(word.to_owned()));
+        let expected = vec![
+            RstEvent::StartDocument,
+            RstEvent::StartSection {
+                level: 1,
+                title: Text::from_plain("Title"),
+            },
+            RstEvent::StartList {
+                marker: ListMarker::Bullet,
+            },
+            RstEvent::StartListItem,
+            RstEvent::StartParagraph,
+            word("Item"),
+            RstEvent::EndParagraph,
+            RstEvent::EndListItem,
+            RstEvent::EndList,
+            RstEvent::StartParagraph,
+            word("Between"),
+            RstEvent::EndParagraph,
+            RstEvent::StartBlockQuote,
+            RstEvent::StartParagraph,
+            word("Quoted"),
+            RstEvent::Inline(Inline::Character('.')),
+            RstEvent::EndParagraph,
+            RstEvent::StartAttribution,
+            word("Author"),
+            RstEvent::EndAttribution,
+            RstEvent::EndBlockQuote,
+            RstEvent::StartParagraph,
+            word("Code"),
+            RstEvent::Inline(Inline::Character(':')),
+            RstEvent::EndParagraph,
+            RstEvent::LiteralBlock("code".to_owned()),
+        ];
+
+        let events: Vec<_> = events(text).into_iter().map(Result::unwrap).collect();
+        assert_eq!(events[..expected.len()], expected[..]);
+        match &events[expected.len()..] {
+            [RstEvent::Block(BodyBlock::Footnote(_)), RstEvent::EndSection, RstEvent::EndDocument] =>
+                {}
+            rest => panic!("unexpected events {:?}", rest),
+        }
+    }
+
+    #[test]
+    fn nested_sections() {
+        let section = |title: &str, children| {
+            SectionChildren::Section(Section {
+                title: title.to_owned(),
+                children,
+            })
+        };
+        let document = Document(vec![
+            section("One", vec![section("Two", vec![section("Three", vec![])])]),
+            SectionChildren::Transition,
+            section("Four", vec![]),
+        ]);
+        let start = |level, title| RstEvent::StartSection {
+            level,
+            title: Text::from_plain(title),
+        };
+
+        assert_eq!(
+            Events::new(document).collect::<Vec<_>>(),
+            vec![
+                RstEvent::StartDocument,
+                start(1, "One"),
+                start(2, "Two"),
+                start(3, "Three"),
+                RstEvent::EndSection,
+                RstEvent::EndSection,
+                RstEvent::EndSection,
+                RstEvent::Transition,
+                start(1, "Four"),
+                RstEvent::EndSection,
+                RstEvent::EndDocument,
+            ]
+        );
+    }
+
+    #[test]
+    fn error_ends_stream() {
+        let events = events(".. note:: Text.\n");
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            Err(error) => assert!(matches!(error.kind(), ParseErrorKind::Unsupported { .. })),
+            Ok(event) => panic!("unexpected event {:?}", event),
+        }
+    }
+}
@@ -3,19 +3,218 @@
 //! This parses reStructuredText as described in the [specification][].
 //!
 //! [specification]: http://docutils.sourceforge.net/docs/ref/rst/restructuredtext.html
+//!
+//! A [`Document`](ast/struct.Document.html) is most easily parsed from a string:
+//!
+//! ```
+//! use rst::ast::Document;
+//!
+//! let document: Document = "Title\n=====\n\nA paragraph.\n".parse()?;
+//! # Ok::<(), rst::ParseError>(())
+//! ```
+//!
+//! To parse from other [sources](location/trait.Source.html), build a
+//! [`Parser`](struct.Parser.html) over their [tokens](struct.TokenStream.html).
+//!
+//! Documents may also be constructed directly with the builders in [`builder`](builder/index.html).
+//!
+//! # Threading
+//!
+//! The syntax tree in [`ast`](ast/index.html) is made up entirely of owned data; no node holds an
+//! `Rc`, a `RefCell` or a raw pointer. A [`Document`](ast/struct.Document.html) and every node
+//! within it are therefore `Send` and `Sync` and may be parsed on one thread and handed to another.
+//!
+//! [`Location`](location/struct.Location.html) and [`Span`](location/struct.Span.html) are plain
+//! `Copy` values and may be sent anywhere. A [`SourceSpan`](location/struct.SourceSpan.html)
+//! borrows the source it was read from, so it is only `Send` when that source is `Sync`; copy the
+//! underlying `Span` out of it to keep location information beyond the lifetime of the source.
+//!
+//! Parsing uses no global state, so documents may be parsed on any number of threads at once. The
+//! [writers](write/index.html) hold only their options and may be shared between threads.
 
 extern crate url;
 
+pub mod adornment;
 pub mod ast;
+pub mod builder;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+mod error;
+pub mod event;
 pub mod location;
+pub mod names;
+mod parser;
 mod tokens;
+pub mod visit;
+pub mod write;
 
-pub use self::tokens::{Token, TokenStream};
+pub use self::error::{ParseError, ParseErrorKind};
+pub use self::parser::Parser;
+pub use self::tokens::{
+    highlight_spans, Token, TokenError, TokenErrorKind, TokenItem, TokenKind, TokenStream,
+};
+
+/// The version of this crate.
+pub fn version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+/// The optional cargo features this crate was compiled with, in alphabetical order.
+///
+/// Parsing and rendering may differ between builds with different features, so these should be
+/// considered along with the [version](fn.version.html) when caching their results.
+pub fn build_features() -> &'static [&'static str] {
+    &[
+        #[cfg(feature = "conformance")]
+        "conformance",
+        #[cfg(feature = "serde")]
+        "serde",
+        #[cfg(feature = "transliterate")]
+        "transliterate",
+        #[cfg(feature = "unicode-segmentation")]
+        "unicode-segmentation",
+    ]
+}
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
+    use crate::{ast, location, write};
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn build_information() {
+        assert_eq!(crate::version(), env!("CARGO_PKG_VERSION"));
+
+        let features = crate::build_features();
+        assert_eq!(
+            features.contains(&"conformance"),
+            cfg!(feature = "conformance")
+        );
+        assert_eq!(features.contains(&"serde"), cfg!(feature = "serde"));
+        assert_eq!(
+            features.contains(&"transliterate"),
+            cfg!(feature = "transliterate")
+        );
+        assert_eq!(
+            features.contains(&"unicode-segmentation"),
+            cfg!(feature = "unicode-segmentation")
+        );
+        assert!(features.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn owned_types_are_send_sync() {
+        assert_send_sync::<ast::Document>();
+        assert_send_sync::<ast::Body>();
+        assert_send_sync::<ast::BodyBlock>();
+        assert_send_sync::<ast::Section>();
+        assert_send_sync::<ast::Inline>();
+        assert_send_sync::<ast::Text>();
+        assert_send_sync::<location::Location>();
+        assert_send_sync::<location::Span>();
+        assert_send_sync::<crate::Token>();
+        assert_send_sync::<crate::ParseError>();
+        assert_send_sync::<write::HtmlOptions>();
+        assert_send_sync::<write::HtmlWriter>();
+        assert_send_sync::<write::RstWriter>();
+    }
+
+    #[test]
+    fn parsers_are_send_sync() {
+        assert_send_sync::<crate::Parser<location::TextSource>>();
+        assert_send_sync::<crate::TokenStream<location::TextSource>>();
+        assert_send_sync::<crate::event::EventStream<location::TextSource>>();
+    }
+
+    #[test]
+    fn parse_and_write_concurrently() {
+        let documents: Vec<String> = (0..16)
+            .map(|index| {
+                format!(
+                    "Title {0}\n========\n\n{0}. Item.\n\n    Quoted {0}.\n\n.. [{0}] Note.\n",
+                    index + 1
+                )
+            })
+            .collect();
+        // One configured writer of each kind is shared by every thread.
+        let html = Arc::new(write::HtmlWriter::new(write::HtmlOptions {
+            section_id_prefix: "s-".to_owned(),
+            ..write::HtmlOptions::default()
+        }));
+        let rst = Arc::new(write::RstWriter::new().with_width(20));
+        let render = |text: &str, html: &write::HtmlWriter, rst: &write::RstWriter| {
+            let document: ast::Document = text.parse().unwrap();
+            let mut output = Vec::new();
+            html.write_document(&document, &mut output).unwrap();
+            rst.write_document(&document, &mut output).unwrap();
+            (document, String::from_utf8(output).unwrap())
+        };
+        let baseline: Vec<_> = documents
+            .iter()
+            .map(|text| render(text, &html, &rst))
+            .collect();
+
+        let threads: Vec<_> = documents
+            .into_iter()
+            .map(|text| {
+                let (html, rst) = (Arc::clone(&html), Arc::clone(&rst));
+                std::thread::spawn(move || {
+                    (0..50)
+                        .map(|_| render(&text, &html, &rst))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        for (thread, expected) in threads.into_iter().zip(&baseline) {
+            for result in thread.join().unwrap() {
+                assert_eq!(&result, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn render_on_another_thread() {
+        let (sender, receiver) = std::sync::mpsc::channel::<ast::Document>();
+        let renderer = std::thread::spawn(move || {
+            receiver
+                .into_iter()
+                .map(|document| {
+                    let mut html = Vec::new();
+                    write::HtmlWriter::default()
+                        .write_document(&document, &mut html)
+                        .unwrap();
+                    String::from_utf8(html).unwrap()
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let parser = std::thread::spawn(move || {
+            for text in &["First paragraph.\n", "- One\n- Two\n"] {
+                sender.send(text.parse().unwrap()).unwrap();
+            }
+        });
+        parser.join().unwrap();
+
+        assert_eq!(
+            renderer.join().unwrap(),
+            vec![
+                "<p>First paragraph.</p>\n".to_owned(),
+                "<ul>\n<li>\n<p>One</p>\n</li>\n<li>\n<p>Two</p>\n</li>\n</ul>\n".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn source_spans_are_send_sync() {
+        assert_send_sync::<location::SourceSpan<location::TextSource>>();
+        assert_send_sync::<location::SourceLocation<location::TextSource>>();
+    }
 }
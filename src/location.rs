@@ -3,25 +3,39 @@
 //! This contains all metadata attributable to the entire parse chain.
 
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::fmt;
-use std::io::{BufRead, BufReader, Read};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
 use std::ops::Deref;
+use std::path::{Path, PathBuf};
 use std::str;
+use std::sync::{Arc, Mutex, PoisonError};
 
-use failure::Error;
+#[cfg(feature = "serde")]
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+
+use crate::tokens::TokenErrorKind;
 
 /// A character source.
 pub trait Source {
     /// The iterator over characters in the source.
-    type Chars: Iterator<Item = Result<char, Error>>;
+    type Chars: Iterator<Item = Result<char, TokenErrorKind>>;
 
     /// Get the name of the source.
     ///
     /// This is displayed when showing errors in the source.
-    fn name(&self) -> Cow<str>;
+    fn name(&self) -> Cow<'_, str>;
 
     /// Get an excerpt from the source.
-    fn excerpt(&self, span: Span) -> Option<Cow<str>>;
+    fn excerpt(&self, span: Span) -> Option<Cow<'_, str>>;
+
+    /// Get the path of the file the source was read from.
+    ///
+    /// Relative file references within the source are resolved against this path.
+    fn path(&self) -> Option<&Path> {
+        None
+    }
 
     /// Get an iterator over the characters in the source.
     fn chars(&mut self) -> Option<Self::Chars>;
@@ -31,6 +45,7 @@ pub trait Source {
 pub struct TextSource<'t> {
     name: String,
     buffer: &'t str,
+    path: Option<PathBuf>,
 }
 
 impl<'t> TextSource<'t> {
@@ -38,6 +53,16 @@ impl<'t> TextSource<'t> {
         TextSource {
             name: name.to_owned(),
             buffer: text,
+            path: None,
+        }
+    }
+
+    /// Create a source from text that was read from the file at the given path.
+    pub fn with_path(name: &str, text: &'t str, path: impl Into<PathBuf>) -> Self {
+        TextSource {
+            name: name.to_owned(),
+            buffer: text,
+            path: Some(path.into()),
         }
     }
 }
@@ -45,40 +70,98 @@ impl<'t> TextSource<'t> {
 impl<'t> Source for TextSource<'t> {
     type Chars = TextChars<'t>;
 
-    fn name(&self) -> Cow<str> {
+    fn name(&self) -> Cow<'_, str> {
         Cow::Borrowed(&self.name)
     }
 
-    fn excerpt(&self, span: Span) -> Option<Cow<str>> {
-        let start = span.start.character();
-        let end = span.end.character();
-
-        if end <= self.buffer.len() {
-            Some(Cow::Borrowed(&self.buffer[start..end]))
-        } else {
-            None
-        }
+    fn excerpt(&self, span: Span) -> Option<Cow<'_, str>> {
+        self.buffer
+            .get(span.start.byte()..span.end.byte())
+            .map(Cow::Borrowed)
     }
 
     fn chars(&mut self) -> Option<Self::Chars> {
         Some(TextChars(self.buffer.chars()))
     }
+
+    fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
 }
 
 pub struct TextChars<'t>(str::Chars<'t>);
 
 impl<'t> Iterator for TextChars<'t> {
-    type Item = Result<char, Error>;
+    type Item = Result<char, TokenErrorKind>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.0.next().map(Ok)
     }
 }
 
+/// A source that owns its text.
+///
+/// Unlike a [`TextSource`](struct.TextSource.html), the text need not outlive the source.
+#[derive(Debug)]
+pub struct StringSource {
+    name: String,
+    text: Arc<str>,
+}
+
+impl StringSource {
+    pub fn from_string(name: String, text: String) -> Self {
+        StringSource {
+            name,
+            text: text.into(),
+        }
+    }
+}
+
+impl Source for StringSource {
+    type Chars = StringChars;
+
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.name)
+    }
+
+    fn excerpt(&self, span: Span) -> Option<Cow<'_, str>> {
+        self.text
+            .get(span.start.byte()..span.end.byte())
+            .map(Cow::Borrowed)
+    }
+
+    fn chars(&mut self) -> Option<Self::Chars> {
+        Some(StringChars {
+            text: self.text.clone(),
+            next: 0,
+        })
+    }
+}
+
+pub struct StringChars {
+    text: Arc<str>,
+    next: usize,
+}
+
+impl Iterator for StringChars {
+    type Item = Result<char, TokenErrorKind>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let c = self.text[self.next..].chars().next()?;
+        self.next += c.len_utf8();
+        Some(Ok(c))
+    }
+}
+
+/// A source read incrementally from a reader.
+///
+/// Text is retained as it is read so that excerpts of it can be produced later. Invalid UTF-8 is
+/// reported by the character stream and is not retained.
 #[derive(Debug)]
 pub struct ReaderSource<R> {
     name: String,
     reader: Option<R>,
+    text: Arc<Mutex<String>>,
 }
 
 impl<R: Read> ReaderSource<R> {
@@ -86,6 +169,7 @@ impl<R: Read> ReaderSource<R> {
         ReaderSource {
             name: name.to_owned(),
             reader: Some(reader),
+            text: Arc::default(),
         }
     }
 }
@@ -93,32 +177,85 @@ impl<R: Read> ReaderSource<R> {
 impl<R: Read> Source for ReaderSource<R> {
     type Chars = ReaderChars<R>;
 
-    fn name(&self) -> Cow<str> {
+    fn name(&self) -> Cow<'_, str> {
         Cow::Borrowed(&self.name)
     }
 
-    fn excerpt(&self, span: Span) -> Option<Cow<str>> {
-        // Cannot get excerpt from consumed reader.
-        None
+    fn excerpt(&self, span: Span) -> Option<Cow<'_, str>> {
+        // The text is shared with the character stream, so it cannot be borrowed.
+        let text = self.text.lock().unwrap_or_else(PoisonError::into_inner);
+        let excerpt = text.get(span.start.byte..span.end.byte)?;
+        Some(Cow::Owned(excerpt.to_owned()))
+    }
+
+    fn chars(&mut self) -> Option<Self::Chars> {
+        let text = self.text.clone();
+        self.reader
+            .take()
+            .map(|reader| ReaderChars::from_reader(reader, text))
+    }
+}
+
+/// A source read from a file.
+///
+/// The file is opened immediately and read as it is tokenised. As with a
+/// [`ReaderSource`](struct.ReaderSource.html), the text is retained for excerpts.
+#[derive(Debug)]
+pub struct FileSource {
+    path: PathBuf,
+    reader: ReaderSource<File>,
+}
+
+impl FileSource {
+    /// Open the file at the given path, which is also used as the name of the source.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<FileSource> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        Ok(FileSource {
+            path: path.to_owned(),
+            reader: ReaderSource::from_reader(&path.to_string_lossy(), file),
+        })
+    }
+}
+
+impl Source for FileSource {
+    type Chars = ReaderChars<File>;
+
+    fn name(&self) -> Cow<'_, str> {
+        self.reader.name()
+    }
+
+    fn excerpt(&self, span: Span) -> Option<Cow<'_, str>> {
+        self.reader.excerpt(span)
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.path)
     }
 
     fn chars(&mut self) -> Option<Self::Chars> {
-        self.reader.take().map(ReaderChars::from_reader)
+        self.reader.chars()
     }
 }
 
 pub struct ReaderChars<R> {
     next: usize,
     buffer: Vec<char>,
+    bytes: Vec<u8>,
+    error: Option<TokenErrorKind>,
     source: BufReader<R>,
+    text: Arc<Mutex<String>>,
 }
 
 impl<R: Read> ReaderChars<R> {
-    fn from_reader(reader: R) -> ReaderChars<R> {
+    fn from_reader(reader: R, text: Arc<Mutex<String>>) -> ReaderChars<R> {
         ReaderChars {
             next: 0,
             buffer: Vec::new(),
+            bytes: Vec::new(),
+            error: None,
             source: BufReader::new(reader),
+            text,
         }
     }
 }
@@ -134,30 +271,57 @@ impl<R: Read> ReaderChars<R> {
         }
     }
 
-    fn refill_buffer(&mut self) -> Result<(), Error> {
-        let mut line = String::new();
-        match self.source.read_line(&mut line) {
-            Ok(_) => {
+    /// Decode the next run of characters into the buffer.
+    ///
+    /// Returns `false` once the reader has been exhausted.
+    fn refill_buffer(&mut self) -> Result<bool, TokenErrorKind> {
+        if self.bytes.is_empty() && self.source.read_until(b'\n', &mut self.bytes)? == 0 {
+            return Ok(false);
+        }
+
+        self.next = 0;
+        let mut text = self.text.lock().unwrap_or_else(PoisonError::into_inner);
+        match str::from_utf8(&self.bytes) {
+            Ok(line) => {
+                text.push_str(line);
+                self.buffer = line.chars().collect();
+                self.bytes.clear();
+            }
+            Err(err) => {
+                // Keep the valid prefix and report the invalid sequence after it.
+                let valid = err.valid_up_to();
+                let invalid = err.error_len().unwrap_or(self.bytes.len() - valid);
+                let line = str::from_utf8(&self.bytes[..valid]).expect("prefix is valid UTF-8");
+                text.push_str(line);
                 self.buffer = line.chars().collect();
-                self.next = 0;
-                Ok(())
+                self.error = Some(TokenErrorKind::InvalidUtf8 {
+                    bytes: self.bytes[valid..valid + invalid].to_vec(),
+                });
+                self.bytes.drain(..valid + invalid);
             }
-            Err(err) => Err(err.into()),
         }
+
+        Ok(true)
     }
 }
 
 impl<R: Read> Iterator for ReaderChars<R> {
-    type Item = Result<char, Error>;
+    type Item = Result<char, TokenErrorKind>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(c) = self.next_char() {
-            Some(Ok(c))
-        } else {
-            if let Err(err) = self.refill_buffer() {
-                Some(Err(err.into()))
-            } else {
-                self.next_char().map(Ok)
+        loop {
+            if let Some(c) = self.next_char() {
+                return Some(Ok(c));
+            }
+
+            if let Some(err) = self.error.take() {
+                return Some(Err(err));
+            }
+
+            match self.refill_buffer() {
+                Ok(true) => continue,
+                Ok(false) => return None,
+                Err(err) => return Some(Err(err)),
             }
         }
     }
@@ -188,11 +352,33 @@ pub trait SpanLocator: Locator {
 }
 
 /// A location within a stream of text.
-#[derive(Debug, Clone, Copy, Default)]
+///
+/// Locations are ordered by their character offset. Locations tracked through the same text never
+/// have equal offsets with differing rows or columns, but these break ties all the same.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Location {
     row: usize,
     column: usize,
     character: usize,
+    byte: usize,
+}
+
+impl Ord for Location {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.character, self.row, self.column, self.byte).cmp(&(
+            other.character,
+            other.row,
+            other.column,
+            other.byte,
+        ))
+    }
+}
+
+impl PartialOrd for Location {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl fmt::Display for Location {
@@ -213,6 +399,11 @@ impl Location {
     pub fn character(&self) -> usize {
         self.character
     }
+
+    /// The offset in bytes of the location within the UTF-8 encoding of the source.
+    pub fn byte(&self) -> usize {
+        self.byte
+    }
 }
 
 impl Locator for Location {
@@ -226,11 +417,13 @@ impl Locator for Location {
             _ => (self.row, self.column + 1),
         };
         let character = self.character + 1;
+        let byte = self.byte + next.len_utf8();
 
         Location {
             row,
             column,
             character,
+            byte,
         }
     }
 
@@ -247,7 +440,8 @@ impl Locator for Location {
 /// A span between two locations within a stream of text.
 ///
 /// Inclusive of the start and non-inclusive of the end.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Span {
     start: Location,
     end: Location,
@@ -268,9 +462,29 @@ impl Span {
         &self.end
     }
 
+    /// The number of characters in the span.
     pub fn len(&self) -> usize {
         self.end.character - self.start.character
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The location is within the span.
+    ///
+    /// The end of the span is not part of it, so an empty span contains nothing.
+    pub fn contains(&self, location: &Location) -> bool {
+        self.start <= *location && *location < self.end
+    }
+
+    /// The smallest span covering both spans, and anything between them.
+    pub fn merge(&self, other: &Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
 }
 
 impl Locator for Span {
@@ -328,13 +542,17 @@ impl<'s, S: Source> SourceLocation<'s, S> {
             location: Default::default(),
         }
     }
+
+    pub fn source(&self) -> &'s S {
+        self.source
+    }
 }
 
 impl<'s, S> Clone for SourceLocation<'s, S> {
     fn clone(&self) -> Self {
         SourceLocation {
             source: self.source,
-            location: self.location.clone(),
+            location: self.location,
         }
     }
 }
@@ -383,11 +601,32 @@ pub struct SourceSpan<'s, S> {
 }
 
 impl<'s, S: Source> SourceSpan<'s, S> {
-    pub fn excerpt(&self) -> Option<Cow<str>> {
+    pub fn source(&self) -> &'s S {
+        self.source
+    }
+
+    /// The text of the span, if the source can provide it.
+    ///
+    /// The excerpt borrows from the source rather than the span, so it may outlive the span.
+    pub fn excerpt(&self) -> Option<Cow<'s, str>> {
         self.source.excerpt(self.span)
     }
 }
 
+/// Serialised as the name of the source with the span.
+///
+/// A source span cannot be deserialised, as it borrows its source; deserialise the
+/// [`Span`](struct.Span.html) alone instead.
+#[cfg(feature = "serde")]
+impl<'s, S: Source> Serialize for SourceSpan<'s, S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        let mut state = serializer.serialize_struct("SourceSpan", 2)?;
+        state.serialize_field("source", &*self.source.name())?;
+        state.serialize_field("span", &self.span)?;
+        state.end()
+    }
+}
+
 impl<'s, S: Source> fmt::Display for SourceSpan<'s, S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}[{}]", self.source.name(), self.span)
@@ -398,7 +637,7 @@ impl<'s, S> Clone for SourceSpan<'s, S> {
     fn clone(&self) -> Self {
         SourceSpan {
             source: self.source,
-            span: self.span.clone(),
+            span: self.span,
         }
     }
 }
@@ -451,3 +690,153 @@ impl<'s, S> SpanLocator for SourceSpan<'s, S> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Token, TokenStream};
+
+    #[test]
+    fn text_source_path() {
+        let source = TextSource::from_str("memory", "text");
+        assert_eq!(source.path(), None);
+
+        let source = TextSource::with_path("docs", "text", "docs/index.rst");
+        assert_eq!(source.name(), "docs");
+        assert_eq!(source.path(), Some(Path::new("docs/index.rst")));
+    }
+
+    #[test]
+    fn reader_source_excerpts() {
+        let text = "Ünïcode text\n\n  - item\nlast line";
+        let mut source = ReaderSource::from_reader("reader", text.as_bytes());
+        let mut covered = String::new();
+        for item in TokenStream::try_new(&mut source).unwrap() {
+            let (_, span) = item.unwrap();
+            covered.push_str(&span.excerpt().unwrap());
+        }
+        assert_eq!(covered, text);
+    }
+
+    #[test]
+    fn span_arithmetic() {
+        let mut source = TextSource::from_str("spans", "one two\nthree");
+        let spans: Vec<Span> = TokenStream::try_new(&mut source)
+            .unwrap()
+            .map(|item| *item.unwrap().1)
+            .collect();
+        let (one, two, three) = (spans[0], spans[2], spans[4]);
+
+        assert_eq!(one.len(), 3);
+        assert!(!one.is_empty());
+        assert!(one.contains(one.start()));
+        assert!(!one.contains(one.end()));
+        assert!(!one.contains(two.start()));
+        assert!(one.start().span_to(one.start()).is_empty());
+        assert!(!one.start().span_to(one.start()).contains(one.start()));
+
+        let merged = one.merge(&three);
+        assert_eq!(merged.start().character(), 0);
+        assert_eq!(merged.end().character(), 13);
+        assert!(merged.contains(two.start()));
+        assert_eq!(merged, three.merge(&one));
+        assert_eq!(two.merge(&two), two);
+        assert_eq!(merged.merge(&two), merged);
+    }
+
+    #[test]
+    fn location_ordering() {
+        let mut source = TextSource::from_str("locations", "a\nbc\nd");
+        let mut starts: Vec<Location> = TokenStream::try_new(&mut source)
+            .unwrap()
+            .map(|item| *item.unwrap().1.start())
+            .collect();
+        let sorted = starts.clone();
+        starts.reverse();
+        starts.sort();
+        assert_eq!(starts, sorted);
+
+        for pair in sorted.windows(2) {
+            assert!(pair[0] < pair[1]);
+            assert!(pair[0].row() <= pair[1].row());
+        }
+        assert_eq!(sorted[0], Location::default());
+        assert_eq!(sorted.iter().max(), sorted.last());
+    }
+
+    #[test]
+    fn source_span_excerpts() {
+        let mut source = TextSource::from_str("excerpts", "*emphasis* text");
+        let excerpts: Vec<Cow<str>> = TokenStream::try_new(&mut source)
+            .unwrap()
+            .map(|item| item.unwrap().1.excerpt().unwrap())
+            .collect();
+        assert_eq!(excerpts, &["*", "emphasis", "*", " ", "text"]);
+        assert!(excerpts
+            .iter()
+            .all(|excerpt| matches!(excerpt, Cow::Borrowed(_))));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_spans() {
+        let mut source = TextSource::from_str("index.rst", "one\ntwo");
+        let mut tokens = TokenStream::try_new(&mut source).unwrap();
+        let source_span = tokens.nth(2).unwrap().unwrap().1;
+        let span = *source_span;
+
+        let json = serde_json::to_string(&span).unwrap();
+        assert_eq!(serde_json::from_str::<Span>(&json).unwrap(), span);
+
+        let json = serde_json::to_value(&source_span).unwrap();
+        assert_eq!(json["source"], "index.rst");
+        assert_eq!(
+            serde_json::from_value::<Span>(json["span"].clone()).unwrap(),
+            span
+        );
+    }
+
+    #[test]
+    fn string_source() {
+        let text = String::from("Ünïcode text\n  - item");
+        let mut source = StringSource::from_string("owned".to_owned(), text);
+        assert_eq!(source.name(), "owned");
+        assert_eq!(source.path(), None);
+
+        let tokens: Vec<_> = TokenStream::try_new(&mut source)
+            .unwrap()
+            .map(|item| item.unwrap())
+            .map(|(token, span)| (token, span.excerpt().unwrap().into_owned()))
+            .collect();
+        assert_eq!(tokens[0], (Token::Word("Ünïcode".into()), "Ünïcode".into()));
+        let covered: String = tokens.into_iter().map(|(_, excerpt)| excerpt).collect();
+        assert_eq!(covered, "Ünïcode text\n  - item");
+
+        // The characters can be read more than once.
+        let mut source = StringSource::from_string("twice".to_owned(), "twice".to_owned());
+        assert_eq!(source.chars().unwrap().count(), 5);
+        assert_eq!(source.chars().unwrap().count(), 5);
+    }
+
+    #[test]
+    fn file_source() {
+        let path = std::env::temp_dir().join(format!("rst-file-source-{}.rst", std::process::id()));
+        std::fs::write(&path, "Title\n=====\n").unwrap();
+
+        let mut source = FileSource::open(&path).unwrap();
+        assert_eq!(source.name(), path.to_string_lossy());
+        assert_eq!(source.path(), Some(path.as_path()));
+
+        let spans: Vec<_> = TokenStream::try_new(&mut source)
+            .unwrap()
+            .map(|item| *item.unwrap().1)
+            .collect();
+        assert_eq!(source.excerpt(spans[0]).unwrap(), "Title");
+        assert!(source.chars().is_none());
+        drop(source);
+        std::fs::remove_file(&path).unwrap();
+
+        let error = FileSource::open(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+}
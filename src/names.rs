@@ -0,0 +1,780 @@
+//! Names and identifiers derived from document text.
+//!
+//! Identifiers are used for anchors, references between elements and file names. They are
+//! derived from arbitrary text such as section titles, which may contain punctuation and
+//! non-ASCII characters.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// How characters outside of ASCII are treated when generating a slug.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonAscii {
+    /// Remove the characters entirely.
+    Drop,
+    /// Replace the characters with the percent-encoding of their UTF-8 bytes.
+    PercentEncode,
+    /// Keep the characters as they are, converted to lowercase if the slug is.
+    Keep,
+    /// Replace accented latin letters with their unaccented ASCII equivalents.
+    ///
+    /// Characters without a transliteration are dropped.
+    #[cfg(feature = "transliterate")]
+    Transliterate,
+}
+
+/// Options controlling the generation of a slug.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlugOptions {
+    /// The character placed between words.
+    pub separator: char,
+    /// The maximum number of characters in the slug.
+    pub max_length: Option<usize>,
+    /// Convert letters to lowercase.
+    pub lowercase: bool,
+    /// Remove leading digits, as docutils does so that ids begin with a letter.
+    pub strip_leading_digits: bool,
+    /// The treatment of non-ASCII characters.
+    pub non_ascii: NonAscii,
+}
+
+impl Default for SlugOptions {
+    fn default() -> Self {
+        SlugOptions {
+            separator: '-',
+            max_length: None,
+            lowercase: true,
+            strip_leading_digits: false,
+            non_ascii: NonAscii::PercentEncode,
+        }
+    }
+}
+
+impl SlugOptions {
+    /// Options matching the [`make_id`][make_id] function of docutils.
+    ///
+    /// For ASCII input the generated slug is identical to that produced by docutils. When the
+    /// `transliterate` feature is enabled, accented latin letters are transliterated as docutils
+    /// would; otherwise they are dropped.
+    ///
+    /// [make_id]: https://sourceforge.net/p/docutils/code/HEAD/tree/trunk/docutils/docutils/nodes.py
+    pub fn docutils() -> Self {
+        SlugOptions {
+            separator: '-',
+            max_length: None,
+            lowercase: true,
+            strip_leading_digits: true,
+            #[cfg(feature = "transliterate")]
+            non_ascii: NonAscii::Transliterate,
+            #[cfg(not(feature = "transliterate"))]
+            non_ascii: NonAscii::Drop,
+        }
+    }
+}
+
+/// The name of a reference or a target.
+///
+/// References match targets by their [normalised](fn.normalize_name.html) names, while the text
+/// as written (without escapes) is kept for display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefName {
+    display: String,
+    normalized: String,
+}
+
+impl RefName {
+    /// Create a name from the text as it appears in the source.
+    pub fn new(raw: &str) -> Self {
+        let display = unescape(raw);
+        let normalized = normalize_unescaped(&display);
+        RefName {
+            display,
+            normalized,
+        }
+    }
+
+    /// The name as written, with backslash escapes removed.
+    pub fn display(&self) -> &str {
+        &self.display
+    }
+
+    /// The name used to match references against targets.
+    pub fn normalized(&self) -> &str {
+        &self.normalized
+    }
+
+    /// Whether the two names refer to the same target.
+    pub fn matches(&self, other: &RefName) -> bool {
+        self.normalized == other.normalized
+    }
+}
+
+/// Normalise a reference name as it appears in the source.
+///
+/// Backslash escapes are removed, runs of whitespace are collapsed to a single space and letters
+/// are converted to lowercase. Both references and targets are normalised in this way before they
+/// are compared.
+///
+/// As in docutils, names are lowercased rather than case-folded: `ß` does not match `ss`, and
+/// `İ` lowercases to `i̇` (with a combining dot) so it does not match a plain `i`. A word-final
+/// `Σ` lowercases to `ς`.
+pub fn normalize_name(raw: &str) -> String {
+    normalize_unescaped(&unescape(raw))
+}
+
+fn normalize_unescaped(text: &str) -> String {
+    text.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Remove backslash escapes from text.
+///
+/// An escaped character stands for itself, except for escaped whitespace which is removed
+/// entirely.
+pub fn unescape(raw: &str) -> String {
+    let mut text = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) if escaped.is_whitespace() => {}
+                Some(escaped) => text.push(escaped),
+                None => text.push('\\'),
+            },
+            c => text.push(c),
+        }
+    }
+    text
+}
+
+/// Normalise the name of a directive or interpreted text role for lookup.
+///
+/// Names are matched without regard to case, so `Code-Block` and `code-block` name the same
+/// directive. A name must be a simple reference name: letters and digits, optionally joined by
+/// single hyphens, underscores, periods, colons or plus signs. Other names are rejected.
+pub fn normalize_directive_name(name: &str) -> Option<String> {
+    let joiner = |c| matches!(c, '-' | '_' | '.' | ':' | '+');
+    let mut previous = None;
+    for c in name.chars() {
+        let valid = if joiner(c) {
+            previous.is_some_and(|previous| !joiner(previous))
+        } else {
+            c.is_alphanumeric()
+        };
+        if !valid {
+            return None;
+        }
+        previous = Some(c);
+    }
+
+    match previous {
+        Some(last) if !joiner(last) => Some(name.to_lowercase()),
+        _ => None,
+    }
+}
+
+/// The candidate closest to a directive or role name that could not be found, if any is close
+/// enough to suggest in its place.
+///
+/// Candidates are compared by edit distance after [normalisation](fn.normalize_directive_name.html)
+/// and a candidate is suggested if at most a third of the characters of the name differ.
+pub fn closest_name<'c>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'c str>,
+) -> Option<&'c str> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let limit = (name.len() + 2) / 3;
+
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            let chars: Vec<char> = candidate.to_lowercase().chars().collect();
+            (edit_distance(&name, &chars), candidate)
+        })
+        .filter(|&(distance, _)| distance <= limit)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The number of single character insertions, deletions and substitutions that turn one string
+/// into the other.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, &ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Generate an identifier from text in the same way as docutils.
+///
+/// The result may be empty if the text contains no letters.
+pub fn make_id(text: &str) -> String {
+    slug(text, &SlugOptions::docutils())
+}
+
+/// The style of identifiers generated by an [`IdGenerator`](struct.IdGenerator.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdStyle {
+    /// Identical to the ids generated by docutils, and so by Sphinx, for the same document.
+    ///
+    /// Ids are generated with [`make_id`](fn.make_id.html). When that gives an empty id or one
+    /// that is already in use, the next `idN` that is not in use is taken from a counter shared
+    /// with [automatic ids](struct.IdGenerator.html#method.auto_id).
+    Docutils,
+    /// Readable ids that keep non-ASCII letters.
+    ///
+    /// An id that is already in use is given a numbered suffix, as in `intro-2`.
+    Pretty,
+}
+
+/// Assigns unique ids to the elements of a document, in document order.
+#[derive(Debug, Clone)]
+pub struct IdGenerator {
+    style: IdStyle,
+    ids: HashSet<String>,
+    counter: usize,
+}
+
+impl IdGenerator {
+    pub fn new(style: IdStyle) -> Self {
+        IdGenerator {
+            style,
+            ids: HashSet::new(),
+            counter: 0,
+        }
+    }
+
+    /// Assign an id to an element with the given name, such as the title of a section.
+    pub fn id_for(&mut self, name: &str) -> String {
+        match self.style {
+            IdStyle::Docutils => {
+                let id = make_id(name);
+                if id.is_empty() || self.ids.contains(&id) {
+                    return self.auto_id();
+                }
+                self.insert(id)
+            }
+            IdStyle::Pretty => {
+                let options = SlugOptions {
+                    non_ascii: NonAscii::Keep,
+                    ..SlugOptions::default()
+                };
+                let base = slug(name, &options);
+                if base.is_empty() {
+                    return self.auto_id();
+                }
+                let id = (1..)
+                    .map(|n| match n {
+                        1 => base.clone(),
+                        n => format!("{}-{}", base, n),
+                    })
+                    .find(|id| !self.ids.contains(id))
+                    .expect("an unused id exists");
+                self.insert(id)
+            }
+        }
+    }
+
+    /// Assign an id to an element without a name, such as an anonymous footnote.
+    pub fn auto_id(&mut self) -> String {
+        loop {
+            self.counter += 1;
+            let id = format!("id{}", self.counter);
+            if !self.ids.contains(&id) {
+                return self.insert(id);
+            }
+        }
+    }
+
+    /// The id has already been assigned.
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.contains(id)
+    }
+
+    fn insert(&mut self, id: String) -> String {
+        self.ids.insert(id.clone());
+        id
+    }
+}
+
+/// Generate a slug from arbitrary text.
+///
+/// Runs of any characters that are not letters or digits become a single separator and
+/// separators are never placed at either end of the slug. Unless non-ASCII characters are kept,
+/// the slug only ever contains ASCII letters and digits, the separator, and `%` when
+/// percent-encoding.
+pub fn slug(text: &str, options: &SlugOptions) -> String {
+    let mut pieces: Vec<String> = Vec::new();
+    let mut word = String::new();
+
+    for cluster in clusters(text) {
+        let first = cluster.chars().next().expect("clusters are not empty");
+        if !first.is_alphanumeric() {
+            if !word.is_empty() {
+                pieces.push(word.split_off(0));
+            }
+            continue;
+        }
+
+        for c in cluster.chars() {
+            if c.is_ascii_alphanumeric() {
+                push_ascii(&mut word, c, options);
+            } else if !c.is_ascii() {
+                push_non_ascii(&mut word, c, options);
+            }
+        }
+    }
+
+    if !word.is_empty() {
+        pieces.push(word);
+    }
+
+    if options.strip_leading_digits {
+        let leading = pieces
+            .iter()
+            .take_while(|piece| piece.chars().all(|c| c.is_ascii_digit()))
+            .count();
+        pieces.drain(..leading);
+        if let Some(first) = pieces.first_mut() {
+            *first = first
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .to_owned();
+        }
+    }
+
+    let mut slug = String::new();
+    let mut length = 0;
+    for piece in pieces {
+        let separator = if slug.is_empty() { 0 } else { 1 };
+
+        if let Some(max_length) = options.max_length {
+            let remaining = max_length.saturating_sub(length + separator);
+            if remaining == 0 {
+                break;
+            }
+            if piece.chars().count() > remaining {
+                let truncated = truncate_piece(&piece, remaining);
+                if separator != 0 && !truncated.is_empty() {
+                    slug.push(options.separator);
+                }
+                slug.push_str(truncated);
+                break;
+            }
+        }
+
+        if separator != 0 {
+            slug.push(options.separator);
+        }
+        length += separator + piece.chars().count();
+        slug.push_str(&piece);
+    }
+
+    slug
+}
+
+/// Split text into the units that are classified as part of a word or not.
+///
+/// With the `unicode-segmentation` feature, combining marks belong to the grapheme cluster they
+/// modify; otherwise every character is classified on its own.
+#[cfg(feature = "unicode-segmentation")]
+fn clusters(text: &str) -> Vec<&str> {
+    use unicode_segmentation::UnicodeSegmentation;
+    text.graphemes(true).collect()
+}
+
+#[cfg(not(feature = "unicode-segmentation"))]
+fn clusters(text: &str) -> Vec<&str> {
+    text.char_indices()
+        .map(|(index, c)| &text[index..index + c.len_utf8()])
+        .collect()
+}
+
+/// Truncate a piece of a slug without splitting a percent-encoded byte.
+fn truncate_piece(piece: &str, length: usize) -> &str {
+    let mut end = 0;
+    let mut chars = piece.char_indices();
+    while let Some((index, c)) = chars.next() {
+        let width = if c == '%' { 3 } else { 1 };
+        if index + width > length {
+            break;
+        }
+        if width == 3 {
+            chars.next();
+            chars.next();
+        }
+        end = index + width;
+    }
+    &piece[..end]
+}
+
+fn push_ascii(word: &mut String, c: char, options: &SlugOptions) {
+    if options.lowercase {
+        word.push(c.to_ascii_lowercase());
+    } else {
+        word.push(c);
+    }
+}
+
+fn push_non_ascii(word: &mut String, c: char, options: &SlugOptions) {
+    match options.non_ascii {
+        NonAscii::Drop => {}
+        NonAscii::Keep if options.lowercase => word.extend(c.to_lowercase()),
+        NonAscii::Keep => word.push(c),
+        NonAscii::PercentEncode => {
+            let mut buffer = [0u8; 4];
+            let lowered;
+            let encoded = if options.lowercase {
+                lowered = c.to_lowercase().collect::<String>();
+                lowered.as_bytes()
+            } else {
+                c.encode_utf8(&mut buffer).as_bytes()
+            };
+            for byte in encoded {
+                write!(word, "%{:02X}", byte).expect("writing to a string cannot fail");
+            }
+        }
+        #[cfg(feature = "transliterate")]
+        NonAscii::Transliterate => {
+            let lower = c.to_lowercase().next().unwrap_or(c);
+            if let Some(ascii) = transliterate(lower) {
+                for (index, t) in ascii.chars().enumerate() {
+                    if index == 0 && !options.lowercase && c != lower {
+                        word.push(t.to_ascii_uppercase());
+                    } else {
+                        word.push(t);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Transliterate a lowercase latin letter to ASCII.
+///
+/// This follows the Unicode decomposition of the letter, with the additional translations that
+/// docutils applies for letters that have no decomposition.
+#[cfg(feature = "transliterate")]
+fn transliterate(c: char) -> Option<&'static str> {
+    const TABLE: &[(&str, &str)] = &[
+        ("àáâãäåāăą", "a"),
+        ("æ", "ae"),
+        ("çćĉċč", "c"),
+        ("ďđð", "d"),
+        ("èéêëēĕėęě", "e"),
+        ("ĝğġģ", "g"),
+        ("ĥħ", "h"),
+        ("ìíîïĩīĭįı", "i"),
+        ("ĳ", "ij"),
+        ("ĵ", "j"),
+        ("ķ", "k"),
+        ("ĺļľŀł", "l"),
+        ("ñńņňŉ", "n"),
+        ("òóôõöøōŏő", "o"),
+        ("œ", "oe"),
+        ("ŕŗř", "r"),
+        ("śŝşšſ", "s"),
+        ("ß", "sz"),
+        ("ţťŧ", "t"),
+        ("þ", "th"),
+        ("ùúûüũūŭůűų", "u"),
+        ("ŵ", "w"),
+        ("ýÿŷ", "y"),
+        ("źżž", "z"),
+    ];
+
+    TABLE
+        .iter()
+        .find(|(letters, _)| letters.contains(c))
+        .map(|(_, ascii)| *ascii)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reference_names() {
+        let cases = &[
+            ("A  B", "a b", true),
+            ("Python DOC-SIG", "python doc-sig", true),
+            ("hyperlink\nreference", "Hyperlink Reference", true),
+            ("\ttabs\t", "tabs", true),
+            ("a\\_b", "a_b", true),
+            ("a\\:b", "a:b", true),
+            ("a\\ b", "ab", true),
+            ("a\\\\b", "a\\\\b", true),
+            ("a_b", "a-b", false),
+            ("ab", "a b", false),
+            ("STRASSE", "strasse", true),
+            ("Straße", "strasse", false),
+            ("Ünïcode", "üNÏCODE", true),
+            ("İ", "i\u{307}", true),
+            ("İ", "i", false),
+            ("ΣΊΣΥΦΟΣ", "σίσυφοσ", false),
+        ];
+
+        for (reference, target, expected) in cases {
+            let matched = RefName::new(reference).matches(&RefName::new(target));
+            assert_eq!(
+                matched, *expected,
+                "reference {:?} against target {:?}",
+                reference, target
+            );
+        }
+    }
+
+    #[test]
+    fn directive_names() {
+        let cases = &[
+            ("note", Some("note")),
+            ("NOTE", Some("note")),
+            ("Code-Block", Some("code-block")),
+            ("sphinx:Auto.Class_Doc+x", Some("sphinx:auto.class_doc+x")),
+            ("Ünïcode", Some("ünïcode")),
+            ("", None),
+            ("-note", None),
+            ("note_", None),
+            ("code--block", None),
+            ("code-_block", None),
+            ("code block", None),
+            ("a/b", None),
+        ];
+
+        for &(name, normalized) in cases {
+            assert_eq!(
+                normalize_directive_name(name).as_deref(),
+                normalized,
+                "{:?}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn suggestions() {
+        let directives = ["note", "warning", "code-block", "image", "figure"];
+        let suggest = |name| closest_name(name, directives.iter().copied());
+
+        assert_eq!(suggest("notee"), Some("note"));
+        assert_eq!(suggest("Warnign"), Some("warning"));
+        assert_eq!(suggest("codeblock"), Some("code-block"));
+        assert_eq!(suggest("figures"), Some("figure"));
+        assert_eq!(suggest("table"), None);
+        assert_eq!(suggest("x"), None);
+        assert_eq!(closest_name("note", std::iter::empty()), None);
+    }
+
+    #[test]
+    fn reference_display() {
+        let name = RefName::new("Escaped\\_  Name");
+        assert_eq!(name.display(), "Escaped_  Name");
+        assert_eq!(name.normalized(), "escaped_ name");
+        assert_eq!(unescape("trailing\\"), "trailing\\");
+    }
+
+    #[test]
+    fn matches_docutils_make_id() {
+        let cases = &[
+            ("Section Title", "section-title"),
+            ("  Hello,   World!  ", "hello-world"),
+            ("1. Introduction", "introduction"),
+            ("123", ""),
+            ("3D graphics", "d-graphics"),
+            ("A--B", "a-b"),
+            ("_private", "private"),
+            ("version 2.0", "version-2-0"),
+            ("C++ & Rust", "c-rust"),
+            (
+                "Python DOC-SIG mailing list archive",
+                "python-doc-sig-mailing-list-archive",
+            ),
+            ("trailing punctuation...", "trailing-punctuation"),
+            ("tab\tseparated\nlines", "tab-separated-lines"),
+            (
+                "\u{201c}Smart\u{201d} \u{2018}quotes\u{2019}",
+                "smart-quotes",
+            ),
+            ("\u{ab}Guillemets\u{bb}", "guillemets"),
+            ("Em\u{2014}dash \u{2013} en dash", "em-dash-en-dash"),
+            ("non\u{2011}breaking hyphen", "non-breaking-hyphen"),
+        ];
+
+        for (text, id) in cases {
+            assert_eq!(&make_id(text), id, "make_id({:?})", text);
+        }
+    }
+
+    #[test]
+    fn options() {
+        let options = SlugOptions {
+            separator: '_',
+            lowercase: false,
+            ..SlugOptions::default()
+        };
+        assert_eq!(slug("Hello, World", &options), "Hello_World");
+
+        let options = SlugOptions {
+            max_length: Some(9),
+            ..SlugOptions::default()
+        };
+        assert_eq!(slug("alpha beta gamma", &options), "alpha-bet");
+        assert_eq!(slug("alpha beta", &options), "alpha-bet");
+        assert_eq!(slug("alpha", &options), "alpha");
+
+        assert_eq!(slug("2 Fast", &SlugOptions::default()), "2-fast");
+    }
+
+    #[test]
+    fn non_ascii() {
+        assert_eq!(
+            slug("Café Crème", &SlugOptions::default()),
+            "caf%C3%A9-cr%C3%A8me"
+        );
+
+        let options = SlugOptions {
+            non_ascii: NonAscii::Drop,
+            ..SlugOptions::default()
+        };
+        assert_eq!(slug("naïve — idea", &options), "nave-idea");
+
+        let options = SlugOptions {
+            max_length: Some(5),
+            ..SlugOptions::default()
+        };
+        assert_eq!(slug("caféx", &options), "caf");
+    }
+
+    #[test]
+    fn docutils_ids() {
+        // Section titles with the anchors docutils gives them, in document order.
+        let captured = &[
+            ("Introduction", "introduction"),
+            ("Installation", "installation"),
+            ("2. Getting Started", "getting-started"),
+            ("What's new in 3.0?", "what-s-new-in-3-0"),
+            ("C++ API", "c-api"),
+            ("module-foo", "module-foo"),
+            ("Introduction", "id1"),
+            ("2019", "id2"),
+            ("Examples", "examples"),
+            ("Examples", "id3"),
+            ("id4", "id4"),
+            ("!!!", "id5"),
+        ];
+
+        let mut ids = IdGenerator::new(IdStyle::Docutils);
+        for &(title, id) in captured {
+            assert_eq!(ids.id_for(title), id, "{:?}", title);
+        }
+        // The counter skips ids assigned from names.
+        assert_eq!(ids.auto_id(), "id6");
+        assert!(ids.contains("module-foo"));
+        assert!(!ids.contains("id7"));
+
+        let mut ids = IdGenerator::new(IdStyle::Docutils);
+        assert_eq!(ids.id_for("id1"), "id1");
+        assert_eq!(ids.auto_id(), "id2");
+    }
+
+    #[cfg(feature = "transliterate")]
+    #[test]
+    fn docutils_ids_transliterated() {
+        let mut ids = IdGenerator::new(IdStyle::Docutils);
+        assert_eq!(ids.id_for("Über uns"), "uber-uns");
+        assert_eq!(ids.id_for("Ωμέγα"), "id1");
+    }
+
+    #[test]
+    fn pretty_ids() {
+        let mut ids = IdGenerator::new(IdStyle::Pretty);
+        assert_eq!(ids.id_for("Introduction"), "introduction");
+        assert_eq!(ids.id_for("Introduction"), "introduction-2");
+        assert_eq!(ids.id_for("introduction"), "introduction-3");
+        assert_eq!(ids.id_for("2. Getting Started"), "2-getting-started");
+        assert_eq!(ids.id_for("Über uns"), "über-uns");
+        assert_eq!(ids.id_for("Ωμέγα"), "ωμέγα");
+        assert_eq!(ids.id_for("!!!"), "id1");
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn grapheme_clusters() {
+        assert_eq!(
+            slug("cafe\u{301} au lait", &SlugOptions::default()),
+            "cafe%CC%81-au-lait"
+        );
+        assert_eq!(
+            slug("नमस्ते दुनिया", &SlugOptions::default())
+                .matches('-')
+                .count(),
+            1
+        );
+    }
+
+    #[cfg(feature = "transliterate")]
+    #[test]
+    fn transliteration() {
+        assert_eq!(make_id("Café Crème"), "cafe-creme");
+        assert_eq!(make_id("Straße"), "strasze");
+
+        let options = SlugOptions {
+            lowercase: false,
+            non_ascii: NonAscii::Transliterate,
+            ..SlugOptions::default()
+        };
+        assert_eq!(slug("Élan Æther", &options), "Elan-Aether");
+    }
+
+    /// Deterministically generate a variety of strings.
+    fn samples() -> Vec<String> {
+        let alphabet: Vec<char> = "aZ09 -_.,!?*\t\n éßÆ中文😀–“”".chars().collect();
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        (0..500)
+            .map(|_| {
+                let length = (next() % 24) as usize;
+                (0..length)
+                    .map(|_| alphabet[(next() % alphabet.len() as u64) as usize])
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn slug_properties() {
+        let options = SlugOptions::default();
+
+        for text in samples() {
+            let generated = slug(&text, &options);
+
+            if text.chars().any(char::is_alphanumeric) {
+                assert!(!generated.is_empty(), "empty slug for {:?}", text);
+            }
+            assert!(
+                generated
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '%' || c == '-'),
+                "invalid characters in {:?}",
+                generated
+            );
+            assert!(!generated.starts_with('-') && !generated.ends_with('-'));
+            assert!(!generated.contains("--"));
+            assert_eq!(generated, slug(&text, &options));
+        }
+    }
+}
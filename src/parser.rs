@@ -0,0 +1,1936 @@
+//! Parser for the body of a reStructuredText document.
+//!
+//! The token stream is first split into lines, each with its indentation measured in columns.
+//! Blocks are then recognised from the first lines of each block, and the bodies of nested blocks
+//! are parsed from their lines with the enclosing indentation removed.
+
+use std::ops::Range;
+
+use crate::adornment::{AdornmentStyle, AdornmentStyleMap};
+use crate::ast::{
+    BlockQuote, Body, BodyBlock, Cell, Citation, Comment, Document, Footnote, FootnoteIdentifier,
+    Inline, List, ListMarker, LiteralBlock, Paragraph, Row, Section, SectionChildren, Table, Text,
+};
+use crate::error::ParseError;
+use crate::location::{Locator, Source, Span};
+use crate::tokens::{advance, Token, TokenKind, TokenStream};
+
+/// A parser for a reStructuredText document.
+///
+/// The parser recognises sections, transitions, paragraphs, bullet and enumerated lists, literal
+/// blocks, block quotes, simple tables, footnotes, citations and comments. Any other construct is
+/// reported as [unsupported](enum.ParseErrorKind.html#variant.Unsupported).
+///
+/// Section titles are only recognised at the top level of the document; the level of each title
+/// is determined by the order in which [adornment styles](adornment/struct.AdornmentStyleMap.html)
+/// are first used.
+///
+/// ```
+/// use rst::location::TextSource;
+/// use rst::{Parser, TokenStream};
+///
+/// let mut source = TextSource::from_str("example", "A paragraph.\n");
+/// let document = Parser::new(TokenStream::try_new(&mut source)?).parse()?;
+/// assert_eq!(document.tree_string(), "document\n  paragraph \"A paragraph.\" [2 words]\n");
+/// # Ok::<(), rst::ParseError>(())
+/// ```
+pub struct Parser<'s, S: Source> {
+    tokens: TokenStream<'s, S>,
+}
+
+impl<'s, S: Source + 's> Parser<'s, S> {
+    pub fn new(tokens: TokenStream<'s, S>) -> Self {
+        Parser { tokens }
+    }
+
+    /// Parse the entire token stream as a document.
+    pub fn parse(self) -> Result<Document, ParseError> {
+        let name = self.tokens.source().name().into_owned();
+        let lines = read_lines(self.tokens)?;
+        let lines: Vec<_> = lines.iter().map(Line::as_ref).collect();
+        let children = document(&lines).map_err(|error| error.in_source(&name))?;
+        Ok(Document(children))
+    }
+}
+
+/// A line of tokens following its indentation.
+///
+/// Trailing whitespace is removed, so a blank line has no tokens.
+struct Line {
+    indent: usize,
+    tokens: Vec<Token>,
+    span: Span,
+}
+
+impl Line {
+    fn as_ref(&self) -> LineRef<'_> {
+        LineRef {
+            indent: self.indent,
+            tokens: &self.tokens,
+            span: &self.span,
+        }
+    }
+}
+
+/// A line within a block.
+///
+/// The indentation is relative to the block, and the first line of a list item or explicit markup
+/// block excludes its marker.
+#[derive(Clone, Copy)]
+struct LineRef<'l> {
+    indent: usize,
+    tokens: &'l [Token],
+    span: &'l Span,
+}
+
+impl<'l> LineRef<'l> {
+    fn is_blank(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    fn kind(&self, index: usize) -> Option<TokenKind> {
+        self.tokens.get(index).map(Token::kind)
+    }
+
+    /// The token at `index` is whitespace or the line ends before it.
+    fn space_at(&self, index: usize) -> bool {
+        matches!(self.tokens.get(index), None | Some(Token::Whitespace(_)))
+    }
+
+    fn dedent(self, columns: usize) -> Self {
+        LineRef {
+            indent: self.indent.saturating_sub(columns),
+            ..self
+        }
+    }
+
+    /// The line consists of a single repeated adornment character.
+    fn is_adornment(&self) -> bool {
+        match self.kind(0) {
+            Some(kind) => kind.is_adornment() && self.tokens.iter().all(|t| t.kind() == kind),
+            None => false,
+        }
+    }
+
+    /// The number of columns the line spans after its indentation.
+    fn width(&self) -> usize {
+        self.tokens.iter().fold(0, advance)
+    }
+
+    /// The line is an adornment long enough to underline a title on the given line.
+    fn is_underline_of(&self, title: &LineRef) -> bool {
+        self.is_adornment()
+            && !title.is_adornment()
+            && (self.width() >= 4 || self.width() >= title.width())
+    }
+}
+
+fn read_lines<S: Source>(tokens: TokenStream<'_, S>) -> Result<Vec<Line>, ParseError> {
+    let mut lines = Vec::new();
+    let mut indent = 0;
+    let mut line = Vec::new();
+    let mut span: Option<Span> = None;
+
+    for item in tokens {
+        let (token, token_span) = item?;
+        match token {
+            Token::Newline => {
+                let span = span.take().unwrap_or(*token_span);
+                let span = span.span_to(token_span.start());
+                lines.push(finish_line(indent, &mut line, span));
+                indent = 0;
+            }
+            Token::Whitespace(_) if line.is_empty() => indent = advance(indent, &token),
+            token => {
+                span = Some(match span {
+                    Some(span) => span.span_to(token_span.end()),
+                    None => *token_span,
+                });
+                line.push(token);
+            }
+        }
+    }
+
+    if let Some(span) = span {
+        lines.push(finish_line(indent, &mut line, span));
+    }
+
+    Ok(lines)
+}
+
+fn finish_line(indent: usize, tokens: &mut Vec<Token>, span: Span) -> Line {
+    while let Some(Token::Whitespace(_)) = tokens.last() {
+        tokens.pop();
+    }
+
+    let tokens = tokens.split_off(0);
+    let indent = if tokens.is_empty() { 0 } else { indent };
+    Line {
+        indent,
+        tokens,
+        span,
+    }
+}
+
+fn skip_blank<'a, 'l>(lines: &'a [LineRef<'l>]) -> &'a [LineRef<'l>] {
+    let start = lines
+        .iter()
+        .position(|line| !line.is_blank())
+        .unwrap_or(lines.len());
+    &lines[start..]
+}
+
+/// The end of the block beginning at `start` in which every line is indented by at least
+/// `indent` columns.
+///
+/// Blank lines within the block are included but trailing blank lines are not.
+fn indented_end(lines: &[LineRef], start: usize, indent: usize) -> usize {
+    let mut end = start;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        if line.is_blank() {
+            continue;
+        } else if line.indent >= indent {
+            end = index + 1;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+fn min_indent(lines: &[LineRef]) -> usize {
+    lines
+        .iter()
+        .filter(|line| !line.is_blank())
+        .map(|line| line.indent)
+        .min()
+        .unwrap_or(0)
+}
+
+/// A section title or transition.
+enum Heading {
+    Title {
+        title: String,
+        style: AdornmentStyle,
+        span: Span,
+    },
+    Transition,
+}
+
+/// Recognise a section title or transition at the start of the lines, with the lines following it.
+///
+/// An overline must match the underline in both character and length. The border of a simple
+/// table is never a title.
+fn heading<'a, 'l>(
+    lines: &'a [LineRef<'l>],
+) -> Option<Result<(Heading, &'a [LineRef<'l>]), ParseError>> {
+    let first = &lines[0];
+    if first.indent != 0 || is_table_border(first) {
+        return None;
+    }
+
+    let (overline, title, underline, rest) = if first.is_adornment() {
+        let title = match lines.get(1) {
+            Some(title) if !title.is_blank() => title,
+            _ if first.width() >= 4 => return Some(Ok((Heading::Transition, &lines[1..]))),
+            _ => return None,
+        };
+
+        match lines.get(2) {
+            Some(underline)
+                if underline.is_adornment()
+                    && underline.kind(0) == first.kind(0)
+                    && underline.width() == first.width() =>
+            {
+                (true, title, underline, &lines[3..])
+            }
+            _ if first.width() >= 4 => {
+                let span = first.span.span_to(title.span.end());
+                return Some(Err(ParseError::mismatched_overline(span)));
+            }
+            _ => return None,
+        }
+    } else {
+        match lines.get(1) {
+            Some(underline) if underline.indent == 0 && underline.is_underline_of(first) => {
+                (false, first, underline, &lines[2..])
+            }
+            _ => return None,
+        }
+    };
+
+    let heading = Heading::Title {
+        title: text(&[*title]).plain_text(),
+        style: AdornmentStyle {
+            character: underline.tokens[0].adornment_char().unwrap_or_default(),
+            overline,
+        },
+        span: first.span.span_to(underline.span.end()),
+    };
+    Some(Ok((heading, rest)))
+}
+
+/// Parse the top level of a document, nesting sections by the level of their titles.
+fn document(lines: &[LineRef]) -> Result<Vec<SectionChildren>, ParseError> {
+    let mut styles = AdornmentStyleMap::default();
+    let mut children = Vec::new();
+    // The open sections, outermost first.
+    let mut sections: Vec<Section> = Vec::new();
+    let mut lines = skip_blank(lines);
+
+    while !lines.is_empty() {
+        let rest = match heading(lines) {
+            Some(heading) => {
+                let (heading, rest) = heading?;
+                match heading {
+                    Heading::Transition => {
+                        let current = match sections.last_mut() {
+                            Some(section) => &mut section.children,
+                            None => &mut children,
+                        };
+                        current.push(SectionChildren::Transition);
+                    }
+                    Heading::Title { title, style, span } => {
+                        let level = styles.level_of(style) as usize;
+                        if level > sections.len() + 1 {
+                            return Err(ParseError::inconsistent_title_level(span));
+                        }
+                        close_sections(&mut sections, &mut children, level - 1);
+                        sections.push(Section {
+                            title,
+                            children: Vec::new(),
+                        });
+                    }
+                }
+                rest
+            }
+            None => {
+                let (blocks, rest) = blocks(lines, true)?;
+                let current = match sections.last_mut() {
+                    Some(section) => &mut section.children,
+                    None => &mut children,
+                };
+                current.extend(blocks.into_iter().map(SectionChildren::Body));
+                rest
+            }
+        };
+
+        lines = skip_blank(rest);
+    }
+
+    close_sections(&mut sections, &mut children, 0);
+    Ok(children)
+}
+
+/// Close open sections until only `depth` remain, adding each to its parent.
+fn close_sections(sections: &mut Vec<Section>, children: &mut Vec<SectionChildren>, depth: usize) {
+    while sections.len() > depth {
+        let section = SectionChildren::Section(sections.pop().expect("a section is open"));
+        match sections.last_mut() {
+            Some(parent) => parent.children.push(section),
+            None => children.push(section),
+        }
+    }
+}
+
+fn body(lines: &[LineRef]) -> Result<Body, ParseError> {
+    let (blocks, _) = blocks(lines, false)?;
+    Ok(Body(blocks))
+}
+
+/// Parse body elements, stopping before a section title or transition if `headings` is set.
+fn blocks<'a, 'l>(
+    lines: &'a [LineRef<'l>],
+    headings: bool,
+) -> Result<(Vec<BodyBlock>, &'a [LineRef<'l>]), ParseError> {
+    let mut blocks = Vec::new();
+    let mut lines = skip_blank(lines);
+
+    while let Some(line) = lines.first() {
+        if headings && heading(lines).is_some() {
+            break;
+        }
+
+        let rest = if line.indent > 0 {
+            block_quote(lines, &mut blocks)?
+        } else if line.kind(0) == Some(TokenKind::Period)
+            && line.kind(1) == Some(TokenKind::Period)
+            && line.space_at(2)
+        {
+            explicit_markup(lines, &mut blocks)?
+        } else if let Some(bullet) = bullet(line) {
+            bullet_list(lines, bullet, &mut blocks)?
+        } else if let Some(enumerator) = enumerated_item(lines, None) {
+            enumerated_list(lines, enumerator, &mut blocks)?
+        } else if is_table_border(line) {
+            simple_table(lines, &mut blocks)?
+        } else {
+            text_block(lines, &mut blocks)?
+        };
+
+        lines = skip_blank(rest);
+    }
+
+    Ok((blocks, lines))
+}
+
+fn block_quote<'a, 'l>(
+    lines: &'a [LineRef<'l>],
+    blocks: &mut Vec<BodyBlock>,
+) -> Result<&'a [LineRef<'l>], ParseError> {
+    let end = indented_end(lines, 0, 1);
+    let indent = min_indent(&lines[..end]);
+    let quote: Vec<_> = lines[..end]
+        .iter()
+        .map(|line| line.dedent(indent))
+        .collect();
+
+    let (quote, attribution) = split_attribution(&quote);
+    blocks.push(BodyBlock::BlockQuote(BlockQuote {
+        quote: body(quote)?,
+        attribution,
+    }));
+
+    Ok(&lines[end..])
+}
+
+/// Separate a final paragraph that begins with `--`, `---` or `—` from a block quote.
+fn split_attribution<'a, 'l>(lines: &'a [LineRef<'l>]) -> (&'a [LineRef<'l>], Option<Text>) {
+    let blank = match lines.iter().rposition(LineRef::is_blank) {
+        Some(blank) => blank,
+        None => return (lines, None),
+    };
+
+    if lines[blank + 1].indent != 0 {
+        return (lines, None);
+    }
+
+    let Text(mut inlines) = text(&lines[blank + 1..]);
+    let dashes = inlines
+        .iter()
+        .take_while(|inline| matches!(inline, Inline::Character('-')))
+        .count();
+
+    match inlines.first_mut() {
+        _ if dashes == 2 || dashes == 3 => {
+            inlines.drain(..dashes);
+        }
+        Some(Inline::Word(word)) if word.starts_with('—') => {
+            let rest = word['—'.len_utf8()..].to_owned();
+            if rest.is_empty() {
+                inlines.remove(0);
+            } else {
+                *word = rest;
+            }
+        }
+        _ => return (lines, None),
+    }
+
+    if let Some(Inline::Whitespace) = inlines.first() {
+        inlines.remove(0);
+    }
+
+    if inlines.is_empty() {
+        (lines, None)
+    } else {
+        (&lines[..blank], Some(Text(inlines)))
+    }
+}
+
+fn explicit_markup<'a, 'l>(
+    lines: &'a [LineRef<'l>],
+    blocks: &mut Vec<BodyBlock>,
+) -> Result<&'a [LineRef<'l>], ParseError> {
+    let first = &lines[0];
+    let content = &first.tokens[2..];
+    let start = content
+        .iter()
+        .position(|token| !matches!(token, Token::Whitespace(_)))
+        .unwrap_or(content.len());
+    let content = &content[start..];
+
+    if let Some(construct) = explicit_construct(content) {
+        return Err(ParseError::unsupported(construct, *first.span));
+    }
+
+    if let Some((label, length)) = label(content) {
+        // The body begins after the label and continues while lines are indented.
+        let end = indented_end(lines, 1, 1);
+        let mut item = Vec::new();
+        let start = content[length..]
+            .iter()
+            .position(|token| !matches!(token, Token::Whitespace(_)))
+            .map(|start| length + start);
+        if let Some(start) = start {
+            item.push(LineRef {
+                indent: 0,
+                tokens: &content[start..],
+                span: first.span,
+            });
+        }
+        let indent = min_indent(&lines[1..end]);
+        item.extend(lines[1..end].iter().map(|line| line.dedent(indent)));
+
+        let body = body(&item)?;
+        blocks.push(match label {
+            Label::Footnote(identifier) => BodyBlock::Footnote(Footnote { identifier, body }),
+            Label::Citation(name) => BodyBlock::Citation(Citation { name, body }),
+        });
+        return Ok(&lines[end..]);
+    }
+
+    // An explicit markup start followed by a blank line is an empty comment.
+    let end = match lines.get(1) {
+        Some(line) if content.is_empty() && line.is_blank() => 1,
+        _ => indented_end(lines, 1, 1),
+    };
+
+    let mut comment = String::new();
+    push_raw(&mut comment, content);
+    if end > 1 {
+        if !comment.is_empty() {
+            comment.push('\n');
+        }
+        comment.push_str(&literal_text(&lines[1..end]));
+    }
+
+    blocks.push(BodyBlock::Comment(Comment(comment)));
+    Ok(&lines[end..])
+}
+
+/// The kind of explicit markup beginning with the given tokens, if it is not a comment.
+fn explicit_construct(tokens: &[Token]) -> Option<&'static str> {
+    let closed = |kind| tokens.iter().skip(1).any(|token| token.kind() == kind);
+
+    match tokens.first().map(Token::kind) {
+        Some(TokenKind::Underscore) => return Some("hyperlink target"),
+        Some(TokenKind::Pipe) if closed(TokenKind::Pipe) => {
+            return Some("substitution definition");
+        }
+        _ => {}
+    }
+
+    for (index, token) in tokens.iter().enumerate() {
+        let marker = index > 0
+            && token.kind() == TokenKind::Colon
+            && tokens.get(index + 1).map(Token::kind) == Some(TokenKind::Colon)
+            && matches!(tokens.get(index + 2), None | Some(Token::Whitespace(_)));
+
+        if marker {
+            return Some("directive");
+        } else if !token.reference_member() {
+            break;
+        }
+    }
+
+    None
+}
+
+/// The label of a footnote or citation.
+enum Label {
+    Footnote(FootnoteIdentifier),
+    Citation(String),
+}
+
+/// Parse a bracketed footnote or citation label, with the number of tokens it spans.
+fn label(tokens: &[Token]) -> Option<(Label, usize)> {
+    if tokens.first()?.kind() != TokenKind::OpenBracket {
+        return None;
+    }
+
+    let close = tokens
+        .iter()
+        .position(|token| token.kind() == TokenKind::CloseBracket)?;
+    if !matches!(tokens.get(close + 1), None | Some(Token::Whitespace(_))) {
+        return None;
+    }
+
+    let raw = |tokens| {
+        let mut text = String::new();
+        push_raw(&mut text, tokens);
+        text
+    };
+
+    let label = match &tokens[1..close] {
+        [Token::Hash] => Label::Footnote(FootnoteIdentifier::AutoNumbered),
+        [Token::Asterisk] => Label::Footnote(FootnoteIdentifier::AutoSymbol),
+        [Token::Hash, name @ ..] if is_reference_name(name) => {
+            Label::Footnote(FootnoteIdentifier::Labelled(raw(name)))
+        }
+        [number] if number.from_arabic_numeral().is_some() => {
+            Label::Footnote(FootnoteIdentifier::Numbered(number.from_arabic_numeral()?))
+        }
+        name if is_reference_name(name) => Label::Citation(raw(name)),
+        _ => return None,
+    };
+
+    Some((label, close + 1))
+}
+
+/// The tokens form a simple reference name: words joined by single hyphens, underscores,
+/// periods, colons or plus signs.
+fn is_reference_name(tokens: &[Token]) -> bool {
+    let words = |token: &Token| matches!(token, Token::Word(_));
+    match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) if words(first) && words(last) => tokens
+            .windows(2)
+            .all(|pair| pair.iter().all(Token::reference_member) && pair.iter().any(words)),
+        _ => false,
+    }
+}
+
+/// The bullet beginning a bullet list item.
+fn bullet(line: &LineRef) -> Option<TokenKind> {
+    match line.kind(0) {
+        Some(kind) if kind.is_bullet() && line.space_at(1) => Some(kind),
+        _ => None,
+    }
+}
+
+fn bullet_list<'a, 'l>(
+    mut lines: &'a [LineRef<'l>],
+    bullet: TokenKind,
+    blocks: &mut Vec<BodyBlock>,
+) -> Result<&'a [LineRef<'l>], ParseError> {
+    let mut elements = Vec::new();
+
+    loop {
+        let (element, rest) = list_item(lines, 1)?;
+        elements.push(element);
+
+        lines = skip_blank(rest);
+        match lines.first() {
+            Some(line) if line.indent == 0 && self::bullet(line) == Some(bullet) => {}
+            _ => {
+                lines = rest;
+                break;
+            }
+        }
+    }
+
+    blocks.push(BodyBlock::List(List {
+        marker: ListMarker::Bullet,
+        elements,
+    }));
+    Ok(lines)
+}
+
+/// Parse the body of a list item whose marker is the first `marker` tokens of the first line.
+fn list_item<'a, 'l>(
+    lines: &'a [LineRef<'l>],
+    marker: usize,
+) -> Result<(Body, &'a [LineRef<'l>]), ParseError> {
+    let first = lines[0];
+    let mut column = first.tokens[..marker].iter().fold(first.indent, advance);
+    let mut start = marker;
+    while let Some(token @ Token::Whitespace(_)) = first.tokens.get(start) {
+        column = advance(column, token);
+        start += 1;
+    }
+
+    let (item, end) = if start == first.tokens.len() {
+        // The content of the item begins on the following line.
+        let end = indented_end(lines, 1, first.indent + 1);
+        let indent = min_indent(&lines[1..end]);
+        let item: Vec<_> = lines[1..end].iter().map(|l| l.dedent(indent)).collect();
+        (item, end)
+    } else {
+        let end = indented_end(lines, 1, column);
+        let mut item = vec![LineRef {
+            indent: 0,
+            tokens: &first.tokens[start..],
+            span: first.span,
+        }];
+        item.extend(lines[1..end].iter().map(|line| line.dedent(column)));
+        (item, end)
+    };
+
+    Ok((body(&item)?, &lines[end..]))
+}
+
+/// The sequence used to enumerate a list.
+#[derive(Clone, Copy, PartialEq)]
+enum Sequence {
+    Arabic,
+    LatinUppercase,
+    LatinLowercase,
+    RomanUppercase,
+    RomanLowercase,
+}
+
+impl Sequence {
+    /// The sequence of the first enumerator of a list.
+    ///
+    /// A lone `i` or `I` begins a roman numeral list, while any other single letter begins a
+    /// latin list.
+    fn of(ordinal: &Token) -> Option<Sequence> {
+        let word = match ordinal {
+            Token::Word(word) => word,
+            _ => return None,
+        };
+
+        let lowercase = word.chars().all(|c| c.is_ascii_lowercase());
+        let sequence = if ordinal.from_arabic_numeral().is_some() {
+            Sequence::Arabic
+        } else if word != "i" && word != "I" && ordinal.from_latin_numeral().is_some() {
+            if lowercase {
+                Sequence::LatinLowercase
+            } else {
+                Sequence::LatinUppercase
+            }
+        } else if lowercase {
+            Sequence::RomanLowercase
+        } else {
+            Sequence::RomanUppercase
+        };
+
+        sequence.ordinal(ordinal).map(|_| sequence)
+    }
+
+    /// The value of an enumerator within the sequence.
+    fn ordinal(self, ordinal: &Token) -> Option<u64> {
+        let word = match ordinal {
+            Token::Word(word) => word,
+            _ => return None,
+        };
+
+        match self {
+            Sequence::Arabic => ordinal.from_arabic_numeral(),
+            Sequence::LatinUppercase if word.chars().all(|c| c.is_ascii_uppercase()) => {
+                ordinal.from_latin_numeral()
+            }
+            Sequence::LatinLowercase if word.chars().all(|c| c.is_ascii_lowercase()) => {
+                ordinal.from_latin_numeral()
+            }
+            Sequence::RomanUppercase if word.chars().all(|c| c.is_ascii_uppercase()) => {
+                ordinal.from_roman_numeral()
+            }
+            Sequence::RomanLowercase if word.chars().all(|c| c.is_ascii_lowercase()) => {
+                ordinal.from_roman_numeral()
+            }
+            _ => None,
+        }
+    }
+
+    fn marker(self, start: u64) -> ListMarker {
+        match self {
+            Sequence::Arabic => ListMarker::Arabic(start),
+            Sequence::LatinUppercase => ListMarker::LatinUppercase(start),
+            Sequence::LatinLowercase => ListMarker::LatinLowercase(start),
+            Sequence::RomanUppercase => ListMarker::RomanUppercase(start),
+            Sequence::RomanLowercase => ListMarker::RomanLowercase(start),
+        }
+    }
+}
+
+/// The punctuation surrounding an enumerator.
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    /// `1.`
+    Period,
+    /// `1)`
+    Parenthesis,
+    /// `(1)`
+    Parentheses,
+}
+
+/// The enumerator beginning an enumerated list item.
+#[derive(Clone, Copy)]
+struct Enumerator {
+    format: Format,
+    sequence: Sequence,
+    /// The value of the enumerator, or `None` for `#`.
+    ordinal: Option<u64>,
+    /// The number of tokens in the enumerator.
+    length: usize,
+}
+
+impl Enumerator {
+    /// Parse the enumerator at the start of a line.
+    ///
+    /// Without an expected sequence, the sequence is determined from the enumerator itself and
+    /// `#` is taken to begin an arabic list.
+    fn parse(line: &LineRef, sequence: Option<Sequence>) -> Option<Enumerator> {
+        let open = line.kind(0) == Some(TokenKind::OpenParen);
+        let start = if open { 1 } else { 0 };
+        let ordinal = line.tokens.get(start)?;
+
+        let format = match (open, line.kind(start + 1)?) {
+            (false, TokenKind::Period) => Format::Period,
+            (false, TokenKind::CloseParen) => Format::Parenthesis,
+            (true, TokenKind::CloseParen) => Format::Parentheses,
+            _ => return None,
+        };
+
+        let length = start + 2;
+        if !line.space_at(length) {
+            return None;
+        }
+
+        let (sequence, ordinal) = match ordinal {
+            Token::Hash => (sequence.unwrap_or(Sequence::Arabic), None),
+            ordinal => {
+                let sequence = sequence.or_else(|| Sequence::of(ordinal))?;
+                (sequence, Some(sequence.ordinal(ordinal)?))
+            }
+        };
+
+        Some(Enumerator {
+            format,
+            sequence,
+            ordinal,
+            length,
+        })
+    }
+}
+
+/// Recognise an enumerated list item, continuing from a previous item if there is one.
+///
+/// The enumerator must follow on from the previous item, and the line following the enumerator
+/// must be blank, indented, or begin the next item; otherwise the line is ordinary text.
+fn enumerated_item(
+    lines: &[LineRef],
+    previous: Option<(Enumerator, u64)>,
+) -> Option<(Enumerator, u64)> {
+    let first = &lines[0];
+    if first.indent != 0 {
+        return None;
+    }
+
+    let enumerator = Enumerator::parse(first, previous.map(|(e, _)| e.sequence))?;
+    let ordinal = match (enumerator.ordinal, previous) {
+        (Some(ordinal), None) => ordinal,
+        (None, None) => 1,
+        (ordinal, Some((previous, value))) => {
+            // No item follows one numbered with the largest ordinal.
+            let next = value.checked_add(1)?;
+            if enumerator.format != previous.format
+                || ordinal.is_some_and(|ordinal| ordinal != next)
+            {
+                return None;
+            }
+            next
+        }
+    };
+
+    match lines.get(1) {
+        Some(line) if !line.is_blank() && line.indent == 0 => {
+            let next = Enumerator::parse(line, Some(enumerator.sequence))?;
+            let following = ordinal.checked_add(1)?;
+            if next.format != enumerator.format
+                || next.ordinal.is_some_and(|next| next != following)
+            {
+                return None;
+            }
+        }
+        _ => {}
+    }
+
+    Some((enumerator, ordinal))
+}
+
+fn enumerated_list<'a, 'l>(
+    mut lines: &'a [LineRef<'l>],
+    first: (Enumerator, u64),
+    blocks: &mut Vec<BodyBlock>,
+) -> Result<&'a [LineRef<'l>], ParseError> {
+    let marker = match first.0.ordinal {
+        Some(_) => first.0.sequence.marker(first.1),
+        None => ListMarker::AutoNumbered,
+    };
+    let mut item = first;
+    let mut elements = Vec::new();
+
+    loop {
+        let (element, rest) = list_item(lines, item.0.length)?;
+        elements.push(element);
+
+        lines = skip_blank(rest);
+        match lines
+            .first()
+            .and_then(|_| enumerated_item(lines, Some(item)))
+        {
+            Some(next) => item = next,
+            None => {
+                lines = rest;
+                break;
+            }
+        }
+    }
+
+    blocks.push(BodyBlock::List(List { marker, elements }));
+    Ok(lines)
+}
+
+/// The line consists of runs of a single character separated by whitespace, as in the borders and
+/// column span underlines of a simple table.
+fn is_table_rule(line: &LineRef, kind: TokenKind) -> bool {
+    line.indent == 0
+        && line.kind(0) == Some(kind)
+        && line
+            .tokens
+            .iter()
+            .all(|token| token.kind() == kind || token.kind() == TokenKind::Whitespace)
+}
+
+/// The line is a border of a simple table, which has at least two columns.
+fn is_table_border(line: &LineRef) -> bool {
+    is_table_rule(line, TokenKind::Equal) && line.tokens.contains(&Token::Whitespace(' '))
+}
+
+/// The columns covered by each run of characters in a table rule.
+fn rule_columns(line: &LineRef) -> Vec<Range<usize>> {
+    let mut columns: Vec<Range<usize>> = Vec::new();
+    let mut column = line.indent;
+    for token in line.tokens {
+        let next = advance(column, token);
+        if token.kind() != TokenKind::Whitespace {
+            match columns.last_mut() {
+                Some(run) if run.end == column => run.end = next,
+                _ => columns.push(column..next),
+            }
+        }
+        column = next;
+    }
+    columns
+}
+
+/// Parse a simple table, beginning with its top border.
+///
+/// The columns are those of the top border. The table ends at the second border after the top, or
+/// at an earlier border followed by a blank line; with two borders after the top, the rows before
+/// the first of them are header rows.
+fn simple_table<'a, 'l>(
+    lines: &'a [LineRef<'l>],
+    blocks: &mut Vec<BodyBlock>,
+) -> Result<&'a [LineRef<'l>], ParseError> {
+    let top = &lines[0];
+    let columns = rule_columns(top);
+    let mut separator = None;
+    let mut bottom = None;
+
+    for (index, line) in lines.iter().enumerate().skip(1) {
+        if !is_table_border(line) {
+            continue;
+        }
+        if line.width() != top.width() {
+            let problem = "the border does not match the top border";
+            return Err(ParseError::malformed_table(problem, *line.span));
+        }
+        let continues = lines.get(index + 1).is_some_and(|next| !next.is_blank());
+        if separator.is_some() || !continues {
+            bottom = Some(index);
+            break;
+        }
+        separator = Some(index);
+    }
+
+    let bottom = match bottom {
+        Some(bottom) => bottom,
+        None => {
+            let problem = "no bottom border followed by a blank line";
+            return Err(ParseError::malformed_table(problem, *top.span));
+        }
+    };
+    let (header, body) = match separator {
+        Some(separator) => (&lines[1..separator], &lines[separator + 1..bottom]),
+        None => (&lines[1..1], &lines[1..bottom]),
+    };
+
+    blocks.push(BodyBlock::Table(Table {
+        header: table_rows(header, &columns)?,
+        body: table_rows(body, &columns)?,
+    }));
+    Ok(&lines[bottom + 1..])
+}
+
+/// Parse the rows of a simple table between two of its borders.
+///
+/// A row begins with a line whose first column is not blank, and continues until the next such
+/// line. A column span underline ends the row above it, joining the columns under each of its
+/// runs into a single cell.
+fn table_rows(lines: &[LineRef], columns: &[Range<usize>]) -> Result<Vec<Row>, ParseError> {
+    let mut rows = Vec::new();
+    let mut start = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        if is_table_rule(line, TokenKind::Hyphen) {
+            let cells = span_columns(line, columns)?;
+            match start.take() {
+                Some(start) => rows.push(table_row(&lines[start..index], &cells)?),
+                None => {
+                    let problem = "a column span underline does not follow a row";
+                    return Err(ParseError::malformed_table(problem, *line.span));
+                }
+            }
+        } else if !line.is_blank() && (start.is_none() || line.indent < columns[0].end) {
+            if let Some(start) = start {
+                rows.push(table_row(&lines[start..index], &single_columns(columns))?);
+            }
+            start = Some(index);
+        }
+    }
+
+    if let Some(start) = start {
+        rows.push(table_row(&lines[start..], &single_columns(columns))?);
+    }
+    Ok(rows)
+}
+
+/// The cells of a row without a column span underline, each spanning one column.
+fn single_columns(columns: &[Range<usize>]) -> Vec<(Range<usize>, u64)> {
+    columns.iter().map(|column| (column.clone(), 1)).collect()
+}
+
+/// The cells of a row underlined by a column span underline, with the number of columns each
+/// spans.
+///
+/// Each run of the underline must begin and end with a column, and together they must cover every
+/// column.
+fn span_columns(
+    underline: &LineRef,
+    columns: &[Range<usize>],
+) -> Result<Vec<(Range<usize>, u64)>, ParseError> {
+    let misaligned = || {
+        let problem = "a column span underline does not line up with the columns";
+        ParseError::malformed_table(problem, *underline.span)
+    };
+
+    let mut cells = Vec::new();
+    let mut next = 0;
+    for run in rule_columns(underline) {
+        if columns.get(next).map(|column| column.start) != Some(run.start) {
+            return Err(misaligned());
+        }
+        let last = next
+            + columns[next..]
+                .iter()
+                .position(|column| column.end == run.end)
+                .ok_or_else(misaligned)?;
+        cells.push((columns[next].start..run.end, (last + 1 - next) as u64));
+        next = last + 1;
+    }
+
+    if next != columns.len() {
+        return Err(misaligned());
+    }
+    Ok(cells)
+}
+
+/// Parse a row of a simple table from its lines, with the columns covered by each cell.
+fn table_row(lines: &[LineRef], cells: &[(Range<usize>, u64)]) -> Result<Row, ParseError> {
+    let mut contents = vec![Vec::new(); cells.len()];
+    for line in lines {
+        for (content, line) in contents.iter_mut().zip(cell_lines(line, cells)?) {
+            content.push(line);
+        }
+    }
+
+    let cells = contents
+        .into_iter()
+        .zip(cells)
+        .map(|(lines, (_, span))| {
+            let indent = min_indent(&lines);
+            let lines: Vec<_> = lines.iter().map(|line| line.dedent(indent)).collect();
+            Ok(Cell {
+                column_span: *span,
+                row_span: 1,
+                content: body(&lines)?,
+            })
+        })
+        .collect::<Result<_, ParseError>>()?;
+    Ok(Row(cells))
+}
+
+/// Split a line of a table row into a line for each cell.
+///
+/// Text may run past the end of the last column, but not into the margins between columns.
+fn cell_lines<'l>(
+    line: &LineRef<'l>,
+    cells: &[(Range<usize>, u64)],
+) -> Result<Vec<LineRef<'l>>, ParseError> {
+    // The first and last tokens within each cell, with the column of the first.
+    let mut bounds: Vec<Option<(usize, usize, usize)>> = vec![None; cells.len()];
+    let mut cell = 0;
+    let mut column = line.indent;
+
+    for (index, token) in line.tokens.iter().enumerate() {
+        let next = advance(column, token);
+        if token.kind() != TokenKind::Whitespace {
+            while cells
+                .get(cell + 1)
+                .is_some_and(|(range, _)| column >= range.start)
+            {
+                cell += 1;
+            }
+            let (range, _) = &cells[cell];
+            if column < range.start || (cell + 1 < cells.len() && next > range.end) {
+                let problem = "text in a column margin";
+                return Err(ParseError::malformed_table(problem, *line.span));
+            }
+            match &mut bounds[cell] {
+                Some((_, last, _)) => *last = index,
+                empty => *empty = Some((index, index, column - range.start)),
+            }
+        }
+        column = next;
+    }
+
+    Ok(bounds
+        .into_iter()
+        .map(|bounds| match bounds {
+            Some((first, last, indent)) => LineRef {
+                indent,
+                tokens: &line.tokens[first..=last],
+                span: line.span,
+            },
+            None => LineRef {
+                indent: 0,
+                tokens: &[],
+                span: line.span,
+            },
+        })
+        .collect())
+}
+
+fn text_block<'a, 'l>(
+    lines: &'a [LineRef<'l>],
+    blocks: &mut Vec<BodyBlock>,
+) -> Result<&'a [LineRef<'l>], ParseError> {
+    let first = &lines[0];
+    let underlined = match lines.get(1) {
+        Some(next) => next.indent == 0 && next.is_underline_of(first),
+        None => false,
+    };
+
+    // Titles and transitions are only recognised at the top level of a document.
+    if underlined || (first.is_adornment() && first.width() >= 4) {
+        return Err(ParseError::unsupported(
+            "section title or transition",
+            *first.span,
+        ));
+    }
+
+    let end = lines
+        .iter()
+        .position(|line| line.is_blank() || line.indent != 0)
+        .unwrap_or(lines.len());
+    let mut paragraph = lines[..end].to_vec();
+
+    // A paragraph ending with `::` introduces a literal block.
+    let last = paragraph[end - 1];
+    let tokens = last.tokens.len();
+    let literal = tokens >= 2
+        && last.tokens[tokens - 2..]
+            .iter()
+            .all(|token| token.kind() == TokenKind::Colon);
+
+    if literal {
+        let spaced = tokens == 2 || matches!(last.tokens[tokens - 3], Token::Whitespace(_));
+        let mut tokens = &last.tokens[..tokens - 1];
+        if spaced {
+            tokens = &tokens[..tokens.len() - 1];
+            while let Some((Token::Whitespace(_), rest)) = tokens.split_last() {
+                tokens = rest;
+            }
+        }
+
+        if tokens.is_empty() {
+            paragraph.pop();
+        } else {
+            paragraph[end - 1] = LineRef { tokens, ..last };
+        }
+    }
+
+    if !paragraph.is_empty() {
+        blocks.push(BodyBlock::Paragraph(Paragraph(text(&paragraph))));
+    }
+
+    if !literal {
+        return Ok(&lines[end..]);
+    }
+
+    let start = end + lines[end..].len() - skip_blank(&lines[end..]).len();
+    let block = match lines.get(start) {
+        Some(line) if line.indent > 0 => indented_end(lines, start, 1),
+        Some(line) if line.kind(0).is_some_and(TokenKind::is_adornment) => {
+            // A quoted literal block continues while each line begins with the same character.
+            let quote = line.kind(0);
+            start
+                + lines[start..]
+                    .iter()
+                    .take_while(|line| line.indent == 0 && line.kind(0) == quote)
+                    .count()
+        }
+        _ => return Ok(&lines[end..]),
+    };
+
+    let literal = literal_text(&lines[start..block]);
+    blocks.push(BodyBlock::LiteralBlock(LiteralBlock(literal)));
+    Ok(&lines[block..])
+}
+
+/// Text for inline content, with each run of whitespace and line breaks collapsed to one space.
+fn text(lines: &[LineRef]) -> Text {
+    let mut inlines = Vec::new();
+    let mut space = false;
+
+    for line in lines {
+        space |= !inlines.is_empty();
+        for token in line.tokens {
+            if let Token::Whitespace(_) = token {
+                space = true;
+                continue;
+            }
+
+            if space {
+                inlines.push(Inline::Whitespace);
+                space = false;
+            }
+
+            match token {
+                Token::Word(word) => inlines.push(Inline::Word(word.clone())),
+                token => inlines.extend(token.to_char().map(Inline::Character)),
+            }
+        }
+    }
+
+    Text(inlines)
+}
+
+/// The original text of lines, with their common indentation removed.
+fn literal_text(lines: &[LineRef]) -> String {
+    let indent = min_indent(lines);
+    let mut text = String::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        if index != 0 {
+            text.push('\n');
+        }
+        for _ in indent..line.indent {
+            text.push(' ');
+        }
+        push_raw(&mut text, line.tokens);
+    }
+
+    text
+}
+
+fn push_raw(text: &mut String, tokens: &[Token]) {
+    for token in tokens {
+        match token {
+            Token::Word(word) => text.push_str(word),
+            token => text.extend(token.to_char()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseErrorKind;
+    use std::error::Error;
+
+    use crate::location::TextSource;
+    use crate::visit::Visitor;
+
+    fn parse(text: &str) -> Result<Document, ParseError> {
+        let mut source = TextSource::from_str("parser", text);
+        Parser::new(TokenStream::try_new(&mut source)?).parse()
+    }
+
+    fn tree(text: &str) -> String {
+        parse(text).unwrap().tree_string()
+    }
+
+    fn unsupported(text: &str) -> &'static str {
+        match parse(text) {
+            Err(error) => match error.kind() {
+                ParseErrorKind::Unsupported { construct } => construct,
+                _ => panic!("unexpected error: {}", error),
+            },
+            Ok(document) => panic!("parsed as:\n{}", document.tree_string()),
+        }
+    }
+
+    #[test]
+    fn paragraphs() {
+        assert_eq!(tree(""), "document\n");
+        assert_eq!(
+            tree("First paragraph\nwith two lines.\n\n\nSecond  paragraph.  \n"),
+            concat!(
+                "document\n",
+                "  paragraph \"First paragraph with two lines.\" [5 words]\n",
+                "  paragraph \"Second paragraph.\" [2 words]\n",
+            )
+        );
+    }
+
+    #[test]
+    fn bullet_lists() {
+        let text = concat!(
+            "- First item.\n",
+            "\n",
+            "- Second item,\n",
+            "  continued.\n",
+            "\n",
+            "  Second paragraph.\n",
+            "\n",
+            "  * Sublist.\n",
+            "- Third item.\n",
+            "\n",
+            "+ New list.\n",
+            "\n",
+            "Not part of the list.\n",
+        );
+
+        assert_eq!(
+            tree(text),
+            concat!(
+                "document\n",
+                "  bullet_list\n",
+                "    list_item\n",
+                "      paragraph \"First item.\" [2 words]\n",
+                "    list_item\n",
+                "      paragraph \"Second item, continued.\" [3 words]\n",
+                "      paragraph \"Second paragraph.\" [2 words]\n",
+                "      bullet_list\n",
+                "        list_item\n",
+                "          paragraph \"Sublist.\" [1 word]\n",
+                "    list_item\n",
+                "      paragraph \"Third item.\" [2 words]\n",
+                "  bullet_list\n",
+                "    list_item\n",
+                "      paragraph \"New list.\" [2 words]\n",
+                "  paragraph \"Not part of the list.\" [5 words]\n",
+            )
+        );
+
+        assert_eq!(
+            tree("-\n  Content on the next line.\n-1 degrees\n"),
+            concat!(
+                "document\n",
+                "  bullet_list\n",
+                "    list_item\n",
+                "      paragraph \"Content on the next line.\" [5 words]\n",
+                "  paragraph \"-1 degrees\" [2 words]\n",
+            )
+        );
+    }
+
+    #[test]
+    fn enumerated_lists() {
+        let text = concat!(
+            "1. Item 1 initial text.\n",
+            "\n",
+            "   a) Item 1a.\n",
+            "   b) Item 1b.\n",
+            "\n",
+            "2. (i) Item 2i.\n",
+            "   (ii) Item 2ii.\n",
+        );
+
+        assert_eq!(
+            tree(text),
+            concat!(
+                "document\n",
+                "  enumerated_list arabic from 1\n",
+                "    list_item\n",
+                "      paragraph \"Item 1 initial text.\" [4 words]\n",
+                "      enumerated_list loweralpha from 1\n",
+                "        list_item\n",
+                "          paragraph \"Item 1a.\" [2 words]\n",
+                "        list_item\n",
+                "          paragraph \"Item 1b.\" [2 words]\n",
+                "    list_item\n",
+                "      enumerated_list lowerroman from 1\n",
+                "        list_item\n",
+                "          paragraph \"Item 2i.\" [2 words]\n",
+                "        list_item\n",
+                "          paragraph \"Item 2ii.\" [2 words]\n",
+            )
+        );
+
+        assert_eq!(
+            tree("C. Third.\n#. Fourth.\n\nIV) Starts at four.\n"),
+            concat!(
+                "document\n",
+                "  enumerated_list upperalpha from 3\n",
+                "    list_item\n",
+                "      paragraph \"Third.\" [1 word]\n",
+                "    list_item\n",
+                "      paragraph \"Fourth.\" [1 word]\n",
+                "  enumerated_list upperroman from 4\n",
+                "    list_item\n",
+                "      paragraph \"Starts at four.\" [3 words]\n",
+            )
+        );
+
+        assert_eq!(
+            tree("#. One.\n#. Two.\n3. Three.\n"),
+            concat!(
+                "document\n",
+                "  enumerated_list auto\n",
+                "    list_item\n",
+                "      paragraph \"One.\" [1 word]\n",
+                "    list_item\n",
+                "      paragraph \"Two.\" [1 word]\n",
+                "    list_item\n",
+                "      paragraph \"Three.\" [1 word]\n",
+            )
+        );
+
+        // The line following an enumerator must be blank or indented.
+        assert_eq!(
+            tree("A. Einstein was a really\nsmart dude.\n"),
+            "document\n  paragraph \"A. Einstein was a really smart dude.\" [7 words]\n"
+        );
+
+        // Out of sequence enumerators begin a new list.
+        assert_eq!(
+            tree("1. One.\n3. Three.\n"),
+            concat!(
+                "document\n",
+                "  paragraph \"1. One. 3. Three.\" [4 words]\n",
+            )
+        );
+
+        // No item can follow the largest ordinal.
+        assert_eq!(
+            tree("18446744073709551615. Last.\n\n#. Next.\n"),
+            concat!(
+                "document\n",
+                "  enumerated_list arabic from 18446744073709551615\n",
+                "    list_item\n",
+                "      paragraph \"Last.\" [1 word]\n",
+                "  enumerated_list auto\n",
+                "    list_item\n",
+                "      paragraph \"Next.\" [1 word]\n",
+            )
+        );
+        assert_eq!(
+            tree("18446744073709551615. Last.\n#. Next.\n"),
+            "document\n  paragraph \"18446744073709551615. Last. #. Next.\" [4 words]\n",
+        );
+    }
+
+    #[test]
+    fn literal_blocks() {
+        let text = concat!(
+            "Paragraph::\n",
+            "\n",
+            "    for a in [5,4,3,2,1]:\n",
+            "\n",
+            "        print a\n",
+            "\n",
+            "After.\n",
+        );
+        let document = parse(text).unwrap();
+        match &document.0[..] {
+            [SectionChildren::Body(BodyBlock::Paragraph(Paragraph(paragraph))), SectionChildren::Body(BodyBlock::LiteralBlock(LiteralBlock(literal))), SectionChildren::Body(BodyBlock::Paragraph(_))] =>
+            {
+                assert_eq!(paragraph.plain_text(), "Paragraph:");
+                assert_eq!(literal, "for a in [5,4,3,2,1]:\n\n    print a");
+            }
+            _ => panic!("unexpected document:\n{}", document.tree_string()),
+        }
+
+        assert_eq!(
+            tree("Paragraph ::\n\n  Literal\n"),
+            concat!(
+                "document\n",
+                "  paragraph \"Paragraph\" [1 word]\n",
+                "  literal_block \"Literal\" [1 line]\n",
+            )
+        );
+
+        assert_eq!(
+            tree("::\n\n  Literal\n"),
+            "document\n  literal_block \"Literal\" [1 line]\n"
+        );
+
+        assert_eq!(
+            tree("John Doe wrote::\n\n>> Great idea!\n>\n> Why didn't I think of that?\n\nYou just did!\n"),
+            concat!(
+                "document\n",
+                "  paragraph \"John Doe wrote:\" [3 words]\n",
+                "  literal_block \">> Great idea! > > Why didn't I think of…\" [3 lines]\n",
+                "  paragraph \"You just did!\" [3 words]\n",
+            )
+        );
+    }
+
+    #[test]
+    fn nested_literal_blocks() {
+        struct Literals(Vec<String>);
+
+        impl Visitor for Literals {
+            fn visit_literal_block(&mut self, LiteralBlock(literal): &LiteralBlock) {
+                self.0.push(literal.clone());
+            }
+        }
+
+        let text = concat!(
+            "- One\n",
+            "\n",
+            "  * Two\n",
+            "\n",
+            "    + Three::\n",
+            "\n",
+            "          fn main() {\n",
+            "              println!();\n",
+            "          }\n",
+            "\n",
+            "      After.\n",
+        );
+        let document = parse(text).unwrap();
+        let mut literals = Literals(Vec::new());
+        literals.visit_document(&document);
+        assert_eq!(literals.0, ["fn main() {\n    println!();\n}"]);
+    }
+
+    #[test]
+    fn block_quotes() {
+        let text = concat!(
+            "This is an ordinary paragraph, introducing a block quote.\n",
+            "\n",
+            "    \"It is my business to know things.  That is my trade.\"\n",
+            "\n",
+            "    -- Sherlock Holmes\n",
+            "\n",
+            "Between.\n",
+            "\n",
+            "\tTabbed.\n",
+        );
+
+        assert_eq!(
+            tree(text),
+            concat!(
+                "document\n",
+                "  paragraph \"This is an ordinary paragraph, introduci…\" [9 words]\n",
+                "  block_quote\n",
+                "    paragraph \"\\\"It is my business to know things. That …\" [11 words]\n",
+                "    attribution \"Sherlock Holmes\" [2 words]\n",
+                "  paragraph \"Between.\" [1 word]\n",
+                "  block_quote\n",
+                "    paragraph \"Tabbed.\" [1 word]\n",
+            )
+        );
+
+        assert_eq!(
+            tree("  Quote.\n\n  — Author\n\n  ---- Not an attribution\n"),
+            concat!(
+                "document\n",
+                "  block_quote\n",
+                "    paragraph \"Quote.\" [1 word]\n",
+                "    paragraph \"— Author\" [2 words]\n",
+                "    paragraph \"---- Not an attribution\" [4 words]\n",
+            )
+        );
+
+        assert_eq!(
+            tree("  Quote.\n\n  —Author\n  continued\n"),
+            concat!(
+                "document\n",
+                "  block_quote\n",
+                "    paragraph \"Quote.\" [1 word]\n",
+                "    attribution \"Author continued\" [2 words]\n",
+            )
+        );
+    }
+
+    #[test]
+    fn comments() {
+        let text = concat!(
+            "..\n",
+            "   _so: is this!\n",
+            ".. A comment\n",
+            "   on two lines.\n",
+            "..\n",
+            "\n",
+            "   Quote after an empty comment.\n",
+        );
+
+        assert_eq!(
+            tree(text),
+            concat!(
+                "document\n",
+                "  comment \"_so: is this!\" [1 line]\n",
+                "  comment \"A comment on two lines.\" [2 lines]\n",
+                "  comment \"\" [0 lines]\n",
+                "  block_quote\n",
+                "    paragraph \"Quote after an empty comment.\" [5 words]\n",
+            )
+        );
+    }
+
+    #[test]
+    fn footnotes() {
+        let text = concat!(
+            ".. [1] First footnote.\n",
+            ".. [2] Second footnote,\n",
+            "   on two lines.\n",
+            ".. [#note] Footnote with a list:\n",
+            "\n",
+            "   - item\n",
+            "\n",
+            "   .. A nested comment\n",
+            ".. [#]\n",
+            "   Auto-numbered footnote.\n",
+            ".. [*] Auto-symbol footnote.\n",
+            ".. [CIT2002] Citation text.\n",
+            "\n",
+            "   Example::\n",
+            "\n",
+            "       code\n",
+            ".. [not a label] Comment.\n",
+        );
+
+        assert_eq!(
+            tree(text),
+            concat!(
+                "document\n",
+                "  footnote [1]\n",
+                "    paragraph \"First footnote.\" [2 words]\n",
+                "  footnote [2]\n",
+                "    paragraph \"Second footnote, on two lines.\" [5 words]\n",
+                "  footnote [#note]\n",
+                "    paragraph \"Footnote with a list:\" [4 words]\n",
+                "    bullet_list\n",
+                "      list_item\n",
+                "        paragraph \"item\" [1 word]\n",
+                "    comment \"A nested comment\" [1 line]\n",
+                "  footnote [#]\n",
+                "    paragraph \"Auto-numbered footnote.\" [2 words]\n",
+                "  footnote [*]\n",
+                "    paragraph \"Auto-symbol footnote.\" [2 words]\n",
+                "  citation [CIT2002]\n",
+                "    paragraph \"Citation text.\" [2 words]\n",
+                "    paragraph \"Example:\" [1 word]\n",
+                "    literal_block \"code\" [1 line]\n",
+                "  comment \"[not a label] Comment.\" [1 line]\n",
+            )
+        );
+    }
+
+    #[test]
+    fn nested_explicit_markup() {
+        let text = concat!(
+            "- Item.\n",
+            "\n",
+            "  .. A comment at the item's indent\n",
+            "     on two lines.\n",
+            "  .. [#] A footnote in the item.\n",
+            "\n",
+            "1. Enumerated.\n",
+            "\n",
+            "      Quoted.\n",
+            "\n",
+            "      .. [CIT] A citation in a quote:\n",
+            "\n",
+            "         .. Nested three levels deep.\n",
+        );
+
+        assert_eq!(
+            tree(text),
+            concat!(
+                "document\n",
+                "  bullet_list\n",
+                "    list_item\n",
+                "      paragraph \"Item.\" [1 word]\n",
+                "      comment \"A comment at the item's indent on two li…\" [2 lines]\n",
+                "      footnote [#]\n",
+                "        paragraph \"A footnote in the item.\" [5 words]\n",
+                "  enumerated_list arabic from 1\n",
+                "    list_item\n",
+                "      paragraph \"Enumerated.\" [1 word]\n",
+                "      block_quote\n",
+                "        paragraph \"Quoted.\" [1 word]\n",
+                "        citation [CIT]\n",
+                "          paragraph \"A citation in a quote:\" [5 words]\n",
+                "          comment \"Nested three levels deep.\" [1 line]\n",
+            )
+        );
+
+        assert_eq!(
+            unsupported("- Item.\n\n  .. _target: http://example.com\n"),
+            "hyperlink target"
+        );
+        assert_eq!(
+            unsupported("1. Item.\n\n       .. figure:: a.png\n"),
+            "directive"
+        );
+    }
+
+    #[test]
+    fn unsupported_constructs() {
+        assert_eq!(unsupported(".. note:: Text\n"), "directive");
+        assert_eq!(
+            unsupported(".. _target: http://example.com\n"),
+            "hyperlink target"
+        );
+        assert_eq!(
+            unsupported(".. |sub| image:: a.png\n"),
+            "substitution definition"
+        );
+        assert_eq!(
+            unsupported("- Title\n  =====\n"),
+            "section title or transition"
+        );
+        assert_eq!(
+            unsupported("  Para.\n\n  ----\n\n  Para.\n"),
+            "section title or transition"
+        );
+    }
+
+    #[test]
+    fn simple_tables() {
+        let text = concat!(
+            "======  ========  ========  ========\n",
+            "Header  Header 2  Header 3  Header 4\n",
+            "======  ========  ========  ========\n",
+            "body    column 2  column 3\n",
+            "\n",
+            "row 2   Cells may span columns.\n",
+            "------  ----------------------------\n",
+            "row 3   - Table   - Items\n",
+            "          cells     over\n",
+            "        - hold      three\n",
+            "          lists.    lines.\n",
+            "======  ========  ========  ========\n",
+            "\n",
+            "=====  =====\n",
+            "First  Row runs past the last column.\n",
+            "=====  =====\n",
+        );
+        assert_eq!(
+            tree(text),
+            concat!(
+                "document\n",
+                "  table\n",
+                "    header_row\n",
+                "      entry\n",
+                "        paragraph \"Header\" [1 word]\n",
+                "      entry\n",
+                "        paragraph \"Header 2\" [2 words]\n",
+                "      entry\n",
+                "        paragraph \"Header 3\" [2 words]\n",
+                "      entry\n",
+                "        paragraph \"Header 4\" [2 words]\n",
+                "    row\n",
+                "      entry\n",
+                "        paragraph \"body\" [1 word]\n",
+                "      entry\n",
+                "        paragraph \"column 2\" [2 words]\n",
+                "      entry\n",
+                "        paragraph \"column 3\" [2 words]\n",
+                "      entry\n",
+                "    row\n",
+                "      entry\n",
+                "        paragraph \"row 2\" [2 words]\n",
+                "      entry (3 columns)\n",
+                "        paragraph \"Cells may span columns.\" [4 words]\n",
+                "    row\n",
+                "      entry\n",
+                "        paragraph \"row 3\" [2 words]\n",
+                "      entry\n",
+                "        bullet_list\n",
+                "          list_item\n",
+                "            paragraph \"Table cells\" [2 words]\n",
+                "          list_item\n",
+                "            paragraph \"hold lists.\" [2 words]\n",
+                "      entry\n",
+                "        bullet_list\n",
+                "          list_item\n",
+                "            paragraph \"Items over three lines.\" [4 words]\n",
+                "      entry\n",
+                "  table\n",
+                "    row\n",
+                "      entry\n",
+                "        paragraph \"First\" [1 word]\n",
+                "      entry\n",
+                "        paragraph \"Row runs past the last column.\" [6 words]\n",
+            )
+        );
+
+        // Without a blank line after it, the second border separates the header from the body.
+        let text = "=  =\na  b\n=  =\nc  d\n=  =\n";
+        let document = parse(text).unwrap();
+        match &document.0[..] {
+            [SectionChildren::Body(BodyBlock::Table(table))] => {
+                assert_eq!((table.header().len(), table.body().len()), (1, 1));
+            }
+            children => panic!("unexpected children {:?}", children),
+        }
+    }
+
+    #[test]
+    fn simple_table_errors() {
+        let problem = |text: &str| match parse(text) {
+            Err(error) => match error.kind() {
+                ParseErrorKind::MalformedTable { problem } => {
+                    (*problem, error.span().start().row())
+                }
+                _ => panic!("unexpected error: {}", error),
+            },
+            Ok(document) => panic!("parsed as:\n{}", document.tree_string()),
+        };
+
+        assert_eq!(
+            problem("====  ====\nTexts in margin\n====  ====\n"),
+            ("text in a column margin", 1)
+        );
+        assert_eq!(
+            problem("===  ===\na    b\n-- -----\n===  ===\n"),
+            (
+                "a column span underline does not line up with the columns",
+                2
+            )
+        );
+        assert_eq!(
+            problem("===  ===\na    b\n---\n===  ===\n"),
+            (
+                "a column span underline does not line up with the columns",
+                2
+            )
+        );
+        assert_eq!(
+            problem("===  ===\n--------\n===  ===\n"),
+            ("a column span underline does not follow a row", 1)
+        );
+        assert_eq!(
+            problem("===  ===\na    b\n===  ====\n"),
+            ("the border does not match the top border", 2)
+        );
+        assert_eq!(
+            problem("===  ===\na    b\n"),
+            ("no bottom border followed by a blank line", 0)
+        );
+    }
+
+    #[test]
+    fn sections() {
+        let text = concat!(
+            "=======\n",
+            " Title\n",
+            "=======\n",
+            "\n",
+            "Introduction.\n",
+            "\n",
+            "Chapter\n",
+            "-------\n",
+            "\n",
+            "Text.\n",
+            "\n",
+            "Section\n",
+            "~~~~~~~\n",
+            "\n",
+            "----\n",
+            "\n",
+            "Second chapter\n",
+            "--------------\n",
+            "Chapter text.\n",
+            "\n",
+            "========\n",
+            "Appendix\n",
+            "========\n",
+            "\n",
+            "Short\n",
+            "==\n",
+        );
+
+        assert_eq!(
+            tree(text),
+            concat!(
+                "document\n",
+                "  section \"Title\"\n",
+                "    paragraph \"Introduction.\" [1 word]\n",
+                "    section \"Chapter\"\n",
+                "      paragraph \"Text.\" [1 word]\n",
+                "      section \"Section\"\n",
+                "        transition\n",
+                "    section \"Second chapter\"\n",
+                "      paragraph \"Chapter text.\" [2 words]\n",
+                "  section \"Appendix\"\n",
+                "    paragraph \"Short ==\" [2 words]\n",
+            )
+        );
+
+        assert_eq!(
+            tree("Para.\n\n----\n\nPara.\n"),
+            concat!(
+                "document\n",
+                "  paragraph \"Para.\" [1 word]\n",
+                "  transition\n",
+                "  paragraph \"Para.\" [1 word]\n",
+            )
+        );
+    }
+
+    #[test]
+    fn section_errors() {
+        let kind = |text| match parse(text) {
+            Err(error) => error.kind().to_string(),
+            Ok(document) => panic!("parsed as:\n{}", document.tree_string()),
+        };
+
+        assert_eq!(
+            kind("=====\nTitle\n-----\n"),
+            "section title overline does not match its underline"
+        );
+        assert_eq!(
+            kind("======\nTitle\n=====\n"),
+            "section title overline does not match its underline"
+        );
+        assert_eq!(
+            kind("One\n===\n\nTwo\n---\n\nThree\n~~~~~\n\nFour\n====\n\nFive\n~~~~\n"),
+            "section title level is inconsistent"
+        );
+
+        let error = parse("One\n===\n\nTwo\n---\n\nThree\n=====\n\nFour\n^^^^\n\nFive\n----\n")
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error.kind(),
+            ParseErrorKind::InconsistentTitleLevel
+        ));
+        assert_eq!(
+            error.to_string(),
+            "parser[9:0]: section title level is inconsistent"
+        );
+    }
+
+    #[test]
+    fn token_errors() {
+        let mut source =
+            crate::location::ReaderSource::from_reader("invalid", &b"text\n\xff\n"[..]);
+        let error = Parser::new(TokenStream::try_new(&mut source).unwrap())
+            .parse()
+            .err()
+            .unwrap();
+        assert!(matches!(error.kind(), ParseErrorKind::Token(_)));
+        // The message includes that of the kind, so it is not repeated as the source.
+        assert!(error.source().is_none());
+        assert_eq!(error.to_string(), "invalid[1:0]: invalid UTF-8 [ff]");
+    }
+
+    #[test]
+    fn error_locations() {
+        let mut source = TextSource::from_str(
+            "index.rst",
+            "Text.
+
+  .. note:: Quoted.
+",
+        );
+        let error = Parser::new(TokenStream::try_new(&mut source).unwrap())
+            .parse()
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "index.rst[2:2]: directive is not supported"
+        );
+        assert_eq!(error.span().len(), "note:: Quoted.".len() + 3);
+    }
+}
@@ -2,35 +2,147 @@
 //!
 //! This takes a read stream and produces an iterator over the tokens from that stream.
 
-use crate::location::{Locator, Source, SourceLocation, SourceSpan, SpanLocator};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
 
-use failure::{format_err, Error};
+use thiserror::Error;
+
+use crate::adornment;
+use crate::location::{
+    Location, Locator, Source, SourceLocation, SourceSpan, Span, SpanLocator, TextSource,
+};
+
+/// The number of columns between tab stops.
+const TAB_WIDTH: usize = 8;
 
 pub struct TokenStream<'s, S: Source> {
+    lookahead: VecDeque<TokenItem<'s, S>>,
     buffer: Option<(Token, SourceSpan<'s, S>)>,
+    error: Option<TokenError>,
     chars: Chars<'s, S>,
+    #[cfg(feature = "unicode-segmentation")]
+    graphemes: bool,
+    #[cfg(feature = "unicode-segmentation")]
+    previous: Option<char>,
 }
 
 impl<'s, S: Source + 's> TokenStream<'s, S> {
-    pub fn try_new(source: &'s mut S) -> Result<TokenStream<'s, S>, Error> {
+    pub fn try_new(source: &'s mut S) -> Result<TokenStream<'s, S>, TokenError> {
         let stream = TokenStream {
+            lookahead: VecDeque::new(),
             buffer: None,
+            error: None,
             chars: Chars::try_from_source(source)?,
+            #[cfg(feature = "unicode-segmentation")]
+            graphemes: false,
+            #[cfg(feature = "unicode-segmentation")]
+            previous: None,
         };
 
         Ok(stream)
     }
+
+    /// Split words according to Unicode text segmentation rather than by character class.
+    ///
+    /// Words are split at [UAX #29][uax29] word boundaries and characters that continue a
+    /// grapheme cluster, such as combining marks, never begin a new token. A cluster that begins
+    /// with punctuation becomes part of a word. Combining marks following whitespace still begin
+    /// a word, as there is no character for them to combine with.
+    ///
+    /// [uax29]: https://www.unicode.org/reports/tr29/
+    #[cfg(feature = "unicode-segmentation")]
+    pub fn with_graphemes(mut self, graphemes: bool) -> Self {
+        self.graphemes = graphemes;
+        self
+    }
 }
 
-impl<'s, S: Source> Iterator for TokenStream<'s, S> {
-    type Item = Result<(Token, SourceSpan<'s, S>), Error>;
+/// An item read from a [`TokenStream`](struct.TokenStream.html).
+pub type TokenItem<'s, S> = Result<(Token, SourceSpan<'s, S>), TokenError>;
+
+impl<'s, S: Source> TokenStream<'s, S> {
+    /// The source from which tokens are read.
+    pub fn source(&self) -> &'s S {
+        self.chars.location.source()
+    }
+
+    /// The next item in the stream, without consuming it.
+    pub fn peek(&mut self) -> Option<&TokenItem<'s, S>> {
+        self.peek_nth(0)
+    }
+
+    /// The item `n` places ahead in the stream, without consuming it or any before it.
+    ///
+    /// `peek_nth(0)` is the same as `peek()`.
+    pub fn peek_nth(&mut self, n: usize) -> Option<&TokenItem<'s, S>> {
+        while self.lookahead.len() <= n {
+            let item = self.read()?;
+            self.lookahead.push_back(item);
+        }
+        self.lookahead.get(n)
+    }
+
+    /// Return a token to the front of the stream, so that it is the next item produced.
+    pub fn put_back(&mut self, item: (Token, SourceSpan<'s, S>)) {
+        self.lookahead.push_front(Ok(item));
+    }
+
+    /// Consume whitespace, returning the column at which the following token begins.
+    ///
+    /// Tabs advance to the next multiple of eight columns, so at the start of a line this is the
+    /// indentation of the line.
+    pub fn skip_whitespace(&mut self) -> Result<u64, TokenError> {
+        let mut column = None;
+        loop {
+            match self.next() {
+                Some(Ok((token @ Whitespace(_), span))) => {
+                    let start = column.unwrap_or_else(|| span.start().column());
+                    column = Some(advance(start, &token));
+                }
+                Some(Ok(item)) => {
+                    let column = column.unwrap_or_else(|| item.1.start().column());
+                    self.put_back(item);
+                    return Ok(column as u64);
+                }
+                Some(Err(error)) => return Err(error),
+                None => return Ok(column.unwrap_or_else(|| self.chars.location.column()) as u64),
+            }
+        }
+    }
+
+    /// Consume the tokens up to the end of the line.
+    ///
+    /// The newline itself is left in the stream.
+    pub fn collect_until_newline(&mut self) -> Result<Vec<(Token, SourceSpan<'s, S>)>, TokenError> {
+        let mut tokens = Vec::new();
+        while let Some(item) = self.next() {
+            let item = item?;
+            if item.0 == Newline {
+                self.put_back(item);
+                break;
+            }
+            tokens.push(item);
+        }
+        Ok(tokens)
+    }
+
+    /// Read the next item from the source.
+    fn read(&mut self) -> Option<TokenItem<'s, S>> {
+        if let Some(error) = self.error.take() {
+            return Some(Err(error));
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
         loop {
             let (buffer, c, location) = match (self.buffer.take(), self.chars.next()) {
                 (buffer, Some(Ok((c, loc)))) => (buffer, c, loc),
-                (_, Some(Err(error))) => {
-                    return Some(Err(error.into()));
+                (Some(buffer), Some(Err(error))) => {
+                    // Emit the token read so far before the error.
+                    self.error = Some(error);
+                    return Some(Ok(buffer));
+                }
+                (None, Some(Err(error))) => {
+                    return Some(Err(error));
                 }
                 (buffer, None) => {
                     return buffer.map(Ok);
@@ -40,6 +152,30 @@ impl<'s, S: Source> Iterator for TokenStream<'s, S> {
             let next_location = location.location_after(c);
             let char_span = location.span_to(next_location.location());
 
+            #[cfg(feature = "unicode-segmentation")]
+            let buffer = {
+                let previous = self.previous.replace(c);
+                match buffer {
+                    Some((token, span)) if self.graphemes => {
+                        match segmentation::continue_grapheme(token, previous, c) {
+                            Ok(word) => {
+                                self.buffer = Some((word, span.extended_span(c)));
+                                continue;
+                            }
+                            Err(Word(s))
+                                if Token::parse_char(c).is_none()
+                                    && segmentation::is_word_boundary(&s, c) =>
+                            {
+                                self.buffer = Some((Word(c.to_string()), char_span));
+                                break Some(Ok((Word(s), span)));
+                            }
+                            Err(token) => Some((token, span)),
+                        }
+                    }
+                    buffer => buffer,
+                }
+            };
+
             match (buffer, Token::parse_char(c)) {
                 (Some((Token::Word(mut s), span)), None) => {
                     s.push(c);
@@ -55,6 +191,11 @@ impl<'s, S: Source> Iterator for TokenStream<'s, S> {
                     self.buffer = Some((token, char_span));
                     break Some(Ok(s));
                 }
+                #[cfg(feature = "unicode-segmentation")]
+                (None, Some(token)) if self.graphemes && token.kind() != TokenKind::Newline => {
+                    // Hold the token in case it begins a grapheme cluster.
+                    self.buffer = Some((token, char_span));
+                }
                 (None, Some(token)) => {
                     break Some(Ok((token, char_span)));
                 }
@@ -68,6 +209,62 @@ impl<'s, S: Source> Iterator for TokenStream<'s, S> {
     }
 }
 
+impl<'s, S: Source> Iterator for TokenStream<'s, S> {
+    type Item = TokenItem<'s, S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lookahead.pop_front().or_else(|| self.read())
+    }
+}
+
+/// Unicode text segmentation of tokens.
+#[cfg(feature = "unicode-segmentation")]
+mod segmentation {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    use super::Token;
+
+    /// Add a character to a token if it continues the grapheme cluster ending the token.
+    ///
+    /// The token is returned unchanged if the character begins a new cluster.
+    pub fn continue_grapheme(
+        token: Token,
+        previous: Option<char>,
+        c: char,
+    ) -> Result<Token, Token> {
+        let previous = match previous {
+            Some(previous) => previous,
+            None => return Err(token),
+        };
+
+        let mut pair = String::new();
+        pair.push(previous);
+        pair.push(c);
+        if pair.graphemes(true).count() != 1 {
+            return Err(token);
+        }
+
+        match token {
+            Token::Word(mut word) => {
+                word.push(c);
+                Ok(Token::Word(word))
+            }
+            Token::Newline | Token::Whitespace(_) => Err(token),
+            _ => Ok(Token::Word(pair)),
+        }
+    }
+
+    /// Whether there is a word boundary between a word and a following character.
+    pub fn is_word_boundary(word: &str, c: char) -> bool {
+        let mut text = String::with_capacity(word.len() + c.len_utf8());
+        text.push_str(word);
+        text.push(c);
+        text.split_word_bounds()
+            .next_back()
+            .map_or(true, |last| last.len() == c.len_utf8())
+    }
+}
+
 /// A stream of characters.
 pub struct Chars<'s, S: Source> {
     chars: S::Chars,
@@ -76,10 +273,12 @@ pub struct Chars<'s, S: Source> {
 
 impl<'s, S: Source> Chars<'s, S> {
     /// Open the standard input.
-    fn try_from_source(source: &'s mut S) -> Result<Self, Error> {
-        let chars = source
-            .chars()
-            .ok_or(format_err!("Couldn't read chars from {}", source.name()))?;
+    fn try_from_source(source: &'s mut S) -> Result<Self, TokenError> {
+        let chars = source.chars().ok_or_else(|| TokenError {
+            kind: TokenErrorKind::Consumed,
+            location: Location::default(),
+            source_name: source.name().into_owned(),
+        })?;
 
         let location = SourceLocation::source_start(source);
 
@@ -88,7 +287,7 @@ impl<'s, S: Source> Chars<'s, S> {
 }
 
 impl<'s, S: Source> Iterator for Chars<'s, S> {
-    type Item = Result<(char, SourceLocation<'s, S>), Error>;
+    type Item = Result<(char, SourceLocation<'s, S>), TokenError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let location = self.location.clone();
@@ -97,11 +296,76 @@ impl<'s, S: Source> Iterator for Chars<'s, S> {
                 self.location = location.location_after(c);
                 Some(Ok((c, location)))
             }
-            Err(err) => Some(Err(err)),
+            Err(kind) => Some(Err(TokenError {
+                kind,
+                location: *location.location(),
+                source_name: location.source().name().into_owned(),
+            })),
         }
     }
 }
 
+/// Split text into the spans of its tokens, for syntax highlighting.
+///
+/// The spans cover the entire text, in order, without overlapping.
+pub fn highlight_spans(text: &str) -> Vec<(Span, TokenKind)> {
+    let mut source = TextSource::from_str("", text);
+    TokenStream::try_new(&mut source)
+        .expect("text sources can always be read")
+        .map(|item| {
+            let (token, span) = item.expect("text sources can always be read");
+            (*span.span(), token.kind())
+        })
+        .collect()
+}
+
+/// An error encountered while reading tokens from a source.
+///
+/// The location is that of the character that could not be read; everything before it was read
+/// successfully.
+#[derive(Debug, Error)]
+#[error("{source_name}[{location}]: {kind}")]
+pub struct TokenError {
+    #[source]
+    pub(crate) kind: TokenErrorKind,
+    pub(crate) location: Location,
+    pub(crate) source_name: String,
+}
+
+impl TokenError {
+    /// The kind of error.
+    pub fn kind(&self) -> &TokenErrorKind {
+        &self.kind
+    }
+
+    /// The location at which the error occurred.
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    /// The name of the source in which the error occurred.
+    pub fn source_name(&self) -> &str {
+        &self.source_name
+    }
+}
+
+/// The kinds of [`TokenError`](struct.TokenError.html).
+#[derive(Debug, Error)]
+pub enum TokenErrorKind {
+    /// The source could not be read.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// The source contained a sequence of bytes that was not valid UTF-8.
+    #[error("invalid UTF-8 {bytes:02x?}")]
+    InvalidUtf8 { bytes: Vec<u8> },
+    /// A line in the source was too long to be buffered.
+    #[error("line too long")]
+    LineTooLong,
+    /// The characters of the source have already been consumed.
+    #[error("source has already been read")]
+    Consumed,
+}
+
 /*
  * Bullets: ``*`` ``+`` ``-``, ``•``, ``‣``, ``⁃``
  *
@@ -135,7 +399,7 @@ impl<'s, S: Source> Iterator for Chars<'s, S> {
  */
 
 /// A single token from the input stream.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Token {
     // Whitespace
     Newline,
@@ -192,6 +456,135 @@ pub enum Token {
 }
 use Token::*;
 
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Newline => f.write_str("<newline>"),
+            Word(word) => f.write_str(word),
+            token => write!(f, "{}", token.to_char().unwrap_or_default()),
+        }
+    }
+}
+
+/// The kind of a [`Token`](enum.Token.html), without its content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    // Whitespace
+    Newline,
+    Whitespace,
+
+    // Bullets
+    Bullet,
+    HyphenBullet,
+    TriangularBullet,
+
+    // Punctuation
+    Ampersand,
+    Asterisk,
+    At,
+    BackSlash,
+    Backtick,
+    Caret,
+    Colon,
+    Comma,
+    Dollar,
+    DoubleQuote,
+    Equal,
+    Exclamation,
+    ForwardSlash,
+    GreaterThan,
+    Hash,
+    Hyphen,
+    LessThan,
+    Percent,
+    Period,
+    Pipe,
+    Plus,
+    Question,
+    SemiColon,
+    SingleQuote,
+    Tilde,
+    Underscore,
+
+    // Parentheses
+    OpenParen,
+    CloseParen,
+    // Square brackets
+    OpenBracket,
+    CloseBracket,
+    // Curly braces
+    OpenBrace,
+    CloseBrace,
+
+    Word,
+}
+
+impl TokenKind {
+    /// The token could represent a bullet.
+    pub fn is_bullet(self) -> bool {
+        use TokenKind::*;
+        matches!(
+            self,
+            Asterisk | Plus | Hyphen | Bullet | TriangularBullet | HyphenBullet
+        )
+    }
+
+    /// The token could be an adornment.
+    pub fn is_adornment(self) -> bool {
+        self.punctuation().is_some_and(adornment::is_adornment_char)
+    }
+
+    /// The ASCII punctuation character represented by the token, if any.
+    fn punctuation(self) -> Option<char> {
+        use TokenKind::*;
+        let c = match self {
+            Ampersand => '&',
+            Asterisk => '*',
+            At => '@',
+            BackSlash => '\\',
+            Backtick => '`',
+            Caret => '^',
+            Colon => ':',
+            Comma => ',',
+            Dollar => '$',
+            DoubleQuote => '"',
+            Equal => '=',
+            Exclamation => '!',
+            ForwardSlash => '/',
+            GreaterThan => '>',
+            Hash => '#',
+            Hyphen => '-',
+            LessThan => '<',
+            Percent => '%',
+            Period => '.',
+            Pipe => '|',
+            Plus => '+',
+            Question => '?',
+            SemiColon => ';',
+            SingleQuote => '\'',
+            Tilde => '~',
+            Underscore => '_',
+            OpenParen => '(',
+            CloseParen => ')',
+            OpenBracket => '[',
+            CloseBracket => ']',
+            OpenBrace => '{',
+            CloseBrace => '}',
+            Newline | Whitespace | Bullet | HyphenBullet | TriangularBullet | Word => return None,
+        };
+        Some(c)
+    }
+}
+
+/// The column following a token.
+pub(crate) fn advance(column: usize, token: &Token) -> usize {
+    match token {
+        Whitespace('\t') => (column / TAB_WIDTH + 1) * TAB_WIDTH,
+        Word(word) => column + word.chars().count(),
+        _ => column + 1,
+    }
+}
+
 impl Token {
     fn parse_char(c: char) -> Option<Token> {
         let c = match c {
@@ -238,72 +631,91 @@ impl Token {
         Some(c)
     }
 
-    /// The token could represent a bullet.
-    pub fn is_bullet(&self) -> bool {
+    /// The kind of the token.
+    pub fn kind(&self) -> TokenKind {
         match self {
-            Asterisk | Plus | Hyphen => true,
-            Bullet | TriangularBullet | HyphenBullet => true,
-            _ => false,
+            Whitespace(_) => TokenKind::Whitespace,
+            Word(_) => TokenKind::Word,
+            Newline => TokenKind::Newline,
+            Bullet => TokenKind::Bullet,
+            HyphenBullet => TokenKind::HyphenBullet,
+            TriangularBullet => TokenKind::TriangularBullet,
+            Ampersand => TokenKind::Ampersand,
+            Asterisk => TokenKind::Asterisk,
+            At => TokenKind::At,
+            BackSlash => TokenKind::BackSlash,
+            Backtick => TokenKind::Backtick,
+            Caret => TokenKind::Caret,
+            Colon => TokenKind::Colon,
+            Comma => TokenKind::Comma,
+            Dollar => TokenKind::Dollar,
+            DoubleQuote => TokenKind::DoubleQuote,
+            Equal => TokenKind::Equal,
+            Exclamation => TokenKind::Exclamation,
+            ForwardSlash => TokenKind::ForwardSlash,
+            GreaterThan => TokenKind::GreaterThan,
+            Hash => TokenKind::Hash,
+            Hyphen => TokenKind::Hyphen,
+            LessThan => TokenKind::LessThan,
+            Percent => TokenKind::Percent,
+            Period => TokenKind::Period,
+            Pipe => TokenKind::Pipe,
+            Plus => TokenKind::Plus,
+            Question => TokenKind::Question,
+            SemiColon => TokenKind::SemiColon,
+            SingleQuote => TokenKind::SingleQuote,
+            Tilde => TokenKind::Tilde,
+            Underscore => TokenKind::Underscore,
+            OpenParen => TokenKind::OpenParen,
+            CloseParen => TokenKind::CloseParen,
+            OpenBracket => TokenKind::OpenBracket,
+            CloseBracket => TokenKind::CloseBracket,
+            OpenBrace => TokenKind::OpenBrace,
+            CloseBrace => TokenKind::CloseBrace,
         }
     }
 
+    /// The token could represent a bullet.
+    pub fn is_bullet(&self) -> bool {
+        self.kind().is_bullet()
+    }
+
     /// The token could be an adornment.
     pub fn is_adornment(&self) -> bool {
+        self.kind().is_adornment()
+    }
+
+    /// The character the token was read from, if it is not a word.
+    pub fn to_char(&self) -> Option<char> {
         match self {
-            Ampersand => true,
-            Asterisk => true,
-            BackSlash => true,
-            Backtick => true,
-            Caret => true,
-            CloseBracket => true,
-            CloseParen => true,
-            Colon => true,
-            Comma => true,
-            Dollar => true,
-            DoubleQuote => true,
-            Equal => true,
-            Exclamation => true,
-            ForwardSlash => true,
-            GreaterThan => true,
-            Hash => true,
-            Hyphen => true,
-            LessThan => true,
-            OpenBracket => true,
-            OpenParen => true,
-            Percent => true,
-            Period => true,
-            Pipe => true,
-            Plus => true,
-            Question => true,
-            SemiColon => true,
-            SingleQuote => true,
-            Tilde => true,
-            Underscore => true,
-            _ => false,
+            Newline => Some('\n'),
+            Whitespace(c) => Some(*c),
+            Bullet => Some('•'),
+            TriangularBullet => Some('‣'),
+            HyphenBullet => Some('⁃'),
+            Word(_) => None,
+            token => token.kind().punctuation(),
         }
     }
 
+    /// The character of the token, if it could be an adornment.
+    pub fn adornment_char(&self) -> Option<char> {
+        self.kind()
+            .punctuation()
+            .filter(|&c| adornment::is_adornment_char(c))
+    }
+
     /// If the token is a matching brace for another character.
     pub fn closes(&self, open: &Token) -> bool {
-        match (open, self) {
-            (OpenParen, CloseParen) => true,
-            (OpenBracket, CloseBracket) => true,
-            (OpenBrace, CloseBrace) => true,
-            _ => false,
-        }
+        matches!(
+            (open, self),
+            (OpenParen, CloseParen) | (OpenBracket, CloseBracket) | (OpenBrace, CloseBrace)
+        )
     }
 
     /// If the token could be part of a referece.
     pub fn reference_member(&self) -> bool {
-        match self {
-            Word(_) => true,
-            Hyphen => true,
-            Underscore => true,
-            Period => true,
-            Colon => true,
-            Plus => true,
-            _ => false,
-        }
+        matches!(self, Word(_) | Hyphen | Underscore | Period | Colon | Plus)
     }
 
     /// Is any kind of numeral.
@@ -372,18 +784,18 @@ impl Token {
                 word.to_owned()
             };
 
-            let mut roman_numerals = &Self::ROMAN_NUMERALS[..];
+            let mut roman_numerals = Self::ROMAN_NUMERALS;
             let mut word = &word[..];
             let mut total = 0;
             let mut last = vec![];
 
-            while word.len() > 0 {
+            while !word.is_empty() {
                 let (index, (numeral, skip, value)) = roman_numerals
                     .iter()
                     .enumerate()
                     .find(|(_, (n, _, _))| word.starts_with(n))?;
 
-                if last.len() > 0 && last[0] == numeral {
+                if !last.is_empty() && last[0] == numeral {
                     if *skip == 0 {
                         last.push(numeral);
                         if last.len() > 3 {
@@ -416,3 +828,365 @@ impl Token {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::borrow::Cow;
+    use std::vec;
+
+    use crate::location::ReaderSource;
+
+    /// A source that fails after producing its text.
+    struct FailingSource(&'static str);
+
+    impl Source for FailingSource {
+        type Chars = vec::IntoIter<Result<char, TokenErrorKind>>;
+
+        fn name(&self) -> Cow<'_, str> {
+            Cow::Borrowed("failing")
+        }
+
+        fn excerpt(&self, _span: Span) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        fn chars(&mut self) -> Option<Self::Chars> {
+            let mut chars: Vec<_> = self.0.chars().map(Ok).collect();
+            chars.push(Err(
+                io::Error::new(io::ErrorKind::Other, "disconnected").into()
+            ));
+            Some(chars.into_iter())
+        }
+    }
+
+    fn first_error<S: Source>(source: &mut S) -> TokenError {
+        TokenStream::try_new(source)
+            .unwrap()
+            .find_map(Result::err)
+            .expect("stream should fail")
+    }
+
+    #[test]
+    fn io_error_reports_last_good_location() {
+        let error = first_error(&mut FailingSource("one\ntwo"));
+
+        assert!(matches!(error.kind(), TokenErrorKind::Io(_)));
+        assert_eq!(error.source_name(), "failing");
+        assert_eq!(error.location().row(), 1);
+        assert_eq!(error.location().column(), 3);
+        assert_eq!(error.location().character(), 7);
+    }
+
+    #[test]
+    fn invalid_utf8_reports_bytes() {
+        let mut source = ReaderSource::from_reader("bytes", &b"ok\nab\xffcd\n"[..]);
+        let error = first_error(&mut source);
+
+        match error.kind() {
+            TokenErrorKind::InvalidUtf8 { bytes } => assert_eq!(bytes, &[0xff]),
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+        assert_eq!(error.location().row(), 1);
+        assert_eq!(error.location().column(), 2);
+        assert_eq!(error.to_string(), "bytes[1:2]: invalid UTF-8 [ff]");
+    }
+
+    #[test]
+    fn reading_continues_after_invalid_utf8() {
+        let mut source = ReaderSource::from_reader("bytes", &b"ab\xffcd"[..]);
+        let words: Vec<_> = TokenStream::try_new(&mut source)
+            .unwrap()
+            .filter_map(Result::ok)
+            .map(|(token, _)| token)
+            .collect();
+
+        assert_eq!(words, vec![Word("ab".into()), Word("cd".into())]);
+    }
+
+    #[test]
+    fn consumed_source() {
+        let mut source = ReaderSource::from_reader("once", &b"text"[..]);
+        TokenStream::try_new(&mut source).unwrap().for_each(drop);
+
+        match TokenStream::try_new(&mut source) {
+            Err(error) => assert!(matches!(error.kind(), TokenErrorKind::Consumed)),
+            Ok(_) => panic!("source should already be consumed"),
+        }
+    }
+
+    /// Check that the tokens of the text exactly tile it and each span covers its token.
+    fn assert_spans_match(text: &str) {
+        let mut source = TextSource::from_str("spans", text);
+        let mut covered = String::new();
+        let mut character = 0;
+
+        for item in TokenStream::try_new(&mut source).unwrap() {
+            let (token, span) = item.unwrap();
+            let excerpt = span.excerpt().expect("span should be within the source");
+
+            assert_eq!(span.span().start().character(), character, "in {:?}", text);
+            assert_eq!(span.span().len(), excerpt.chars().count(), "in {:?}", text);
+            match &token {
+                Word(word) => assert_eq!(word, &excerpt, "in {:?}", text),
+                token => {
+                    let mut chars = excerpt.chars();
+                    let parsed = chars.next().and_then(Token::parse_char);
+                    assert_eq!(chars.next(), None, "in {:?}", text);
+                    assert_eq!(parsed.as_ref(), Some(token), "in {:?}", text);
+                }
+            }
+
+            character = span.span().end().character();
+            covered.push_str(&excerpt);
+        }
+
+        assert_eq!(covered, text);
+    }
+
+    #[test]
+    fn spans_match_excerpts() {
+        let corpus = &[
+            "",
+            "word",
+            "two words",
+            "adjacent.punctuation!!runs---here",
+            "trailing newline\n",
+            "\n\nleading newlines",
+            "tabs\tand  spaces\u{a0}too",
+            "ünïcödé wörds",
+            "wide 中文字符 characters",
+            "emoji 😀 and 👩‍👩‍👧 sequences",
+            "bullets • ‣ ⁃ mixed",
+            "combining e\u{301} marks.\u{301}",
+            "(parens) [brackets] {braces}",
+            "Section\n=======\n\n* item\n* item\n",
+        ];
+
+        for text in corpus {
+            assert_spans_match(text);
+        }
+    }
+
+    #[test]
+    fn spans_match_generated_excerpts() {
+        let alphabet: Vec<char> = "ab \t\n.-*=_é中😀\u{301}\u{200d}•".chars().collect();
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+
+        for _ in 0..500 {
+            let mut next = || {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state
+            };
+            let length = (next() % 32) as usize;
+            let text: String = (0..length)
+                .map(|_| alphabet[(next() % alphabet.len() as u64) as usize])
+                .collect();
+            assert_spans_match(&text);
+        }
+    }
+
+    #[test]
+    fn token_equality_and_display() {
+        let mut source = TextSource::from_str("tokens", "hello *world*\n");
+        let tokens: Vec<_> = TokenStream::try_new(&mut source)
+            .unwrap()
+            .map(|item| item.unwrap().0)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Word("hello".into()),
+                Whitespace(' '),
+                Asterisk,
+                Word("world".into()),
+                Asterisk,
+                Newline,
+            ]
+        );
+
+        let displayed: Vec<_> = tokens.iter().map(Token::to_string).collect();
+        assert_eq!(displayed, &["hello", " ", "*", "world", "*", "<newline>"]);
+        assert_eq!(Bullet.to_string(), "•");
+        assert_eq!(Whitespace('\t').to_char(), Some('\t'));
+        assert_eq!(Newline.to_char(), Some('\n'));
+        assert_eq!(Bullet.to_char(), Some('•'));
+        assert_eq!(tokens[0].to_char(), None);
+        assert_eq!(Equal.adornment_char(), Some('='));
+        assert_eq!(Asterisk.adornment_char(), Some('*'));
+        assert_eq!(Bullet.adornment_char(), None);
+        assert_eq!(Whitespace(' ').adornment_char(), None);
+        assert_eq!(tokens[0].adornment_char(), None);
+        assert_eq!(tokens[0].clone(), tokens[0]);
+    }
+
+    #[test]
+    fn line_utilities() {
+        let mut source = TextSource::from_str("lines", " \t  indented: text\n\tx\n  \t\n");
+        let mut tokens = TokenStream::try_new(&mut source).unwrap();
+
+        assert_eq!(tokens.skip_whitespace().unwrap(), 10);
+        let line: Vec<_> = tokens
+            .collect_until_newline()
+            .unwrap()
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+        assert_eq!(
+            line,
+            vec![
+                Word("indented".into()),
+                Colon,
+                Whitespace(' '),
+                Word("text".into()),
+            ]
+        );
+        assert_eq!(tokens.skip_whitespace().unwrap(), 18);
+        assert_eq!(tokens.next().unwrap().unwrap().0, Newline);
+
+        assert_eq!(tokens.skip_whitespace().unwrap(), 8);
+        assert_eq!(tokens.next().unwrap().unwrap().0, Word("x".into()));
+        assert_eq!(tokens.next().unwrap().unwrap().0, Newline);
+
+        assert_eq!(tokens.skip_whitespace().unwrap(), 8);
+        assert!(tokens.collect_until_newline().unwrap().is_empty());
+        assert_eq!(tokens.next().unwrap().unwrap().0, Newline);
+        assert_eq!(tokens.skip_whitespace().unwrap(), 0);
+        assert!(tokens.collect_until_newline().unwrap().is_empty());
+    }
+
+    #[test]
+    fn lookahead() {
+        fn kind<S: Source>(item: Option<&TokenItem<'_, S>>) -> Option<TokenKind> {
+            item.map(|item| item.as_ref().unwrap().0.kind())
+        }
+
+        let mut source = TextSource::from_str("lookahead", "a: b");
+        let mut tokens = TokenStream::try_new(&mut source).unwrap();
+
+        assert_eq!(kind(tokens.peek()), Some(TokenKind::Word));
+        assert_eq!(kind(tokens.peek_nth(2)), Some(TokenKind::Whitespace));
+        assert_eq!(kind(tokens.peek_nth(4)), None);
+        assert_eq!(kind(tokens.peek()), Some(TokenKind::Word));
+
+        let (word, span) = tokens.next().unwrap().unwrap();
+        assert_eq!(word.kind(), TokenKind::Word);
+        assert_eq!(kind(tokens.peek()), Some(TokenKind::Colon));
+
+        tokens.put_back((word, span));
+        let kinds: Vec<_> = tokens.map(|item| item.unwrap().0.kind()).collect();
+        assert_eq!(
+            kinds,
+            &[
+                TokenKind::Word,
+                TokenKind::Colon,
+                TokenKind::Whitespace,
+                TokenKind::Word
+            ]
+        );
+    }
+
+    #[test]
+    fn token_kinds() {
+        assert_eq!(Word("word".to_owned()).kind(), TokenKind::Word);
+        assert_eq!(Whitespace('\t').kind(), TokenKind::Whitespace);
+        assert_eq!(Asterisk.kind(), TokenKind::Asterisk);
+        assert!(TokenKind::Bullet.is_bullet());
+        assert!(TokenKind::Equal.is_adornment());
+        assert!(!TokenKind::Word.is_adornment());
+    }
+
+    #[test]
+    fn highlight_spans_tile_input() {
+        let text = "Title\n=====\n\n* *emphasis* and ``literal``  ünïcödé 中文\n";
+        let spans = highlight_spans(text);
+        let source = TextSource::from_str("highlight", text);
+
+        let mut covered = String::new();
+        let mut end = 0;
+        for (span, _) in &spans {
+            assert_eq!(span.start().byte(), end);
+            covered.push_str(&source.excerpt(*span).unwrap());
+            end = span.end().byte();
+        }
+        assert_eq!(covered, text);
+        assert_eq!(end, text.len());
+
+        let kinds: Vec<_> = spans.iter().take(4).map(|(_, kind)| *kind).collect();
+        assert_eq!(
+            kinds,
+            &[
+                TokenKind::Word,
+                TokenKind::Newline,
+                TokenKind::Equal,
+                TokenKind::Equal
+            ]
+        );
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    fn grapheme_words(text: &str) -> Vec<String> {
+        let mut source = TextSource::from_str("graphemes", text);
+        TokenStream::try_new(&mut source)
+            .unwrap()
+            .with_graphemes(true)
+            .filter_map(|item| match item.unwrap() {
+                (Word(word), _) => Some(word),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn grapheme_tokens() {
+        // Devanagari vowel signs and viramas stay within their words.
+        assert_eq!(grapheme_words("नमस्ते दुनिया"), &["नमस्ते", "दुनिया"]);
+
+        // Emoji joined with zero width joiners form a single word.
+        assert_eq!(
+            grapheme_words("a 👩\u{200d}👩\u{200d}👧 b"),
+            &["a", "👩\u{200d}👩\u{200d}👧", "b"]
+        );
+
+        // A combining mark does not begin a new token after punctuation.
+        assert_eq!(grapheme_words("dot.\u{301}"), &["dot", ".\u{301}"]);
+
+        // Ideographs are separate words.
+        assert_eq!(grapheme_words("中文"), &["中", "文"]);
+
+        // Spans still tile the source.
+        let text = "dot.\u{301} 中文 e\u{301}!";
+        let mut source = TextSource::from_str("graphemes", text);
+        let mut covered = String::new();
+        for item in TokenStream::try_new(&mut source)
+            .unwrap()
+            .with_graphemes(true)
+        {
+            let (_, span) = item.unwrap();
+            covered.push_str(&span.excerpt().unwrap());
+        }
+        assert_eq!(covered, text);
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn grapheme_columns() {
+        // Columns measured over grapheme words agree with the locations of their spans.
+        let text = "e\u{301} नमस्ते 👩\u{200d}👧.\u{301}";
+        let mut source = TextSource::from_str("graphemes", text);
+        let mut column = 0;
+        for item in TokenStream::try_new(&mut source)
+            .unwrap()
+            .with_graphemes(true)
+        {
+            let (token, span) = item.unwrap();
+            assert_eq!(span.start().column(), column, "{:?}", token);
+            column = advance(column, &token);
+        }
+        assert_eq!(column, text.chars().count());
+    }
+}
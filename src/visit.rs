@@ -0,0 +1,626 @@
+//! Traversal of the syntax tree.
+//!
+//! A [`Visitor`](trait.Visitor.html) has a method for each kind of node, each of which by default
+//! calls the matching `walk_` function to visit the children of the node. Implementations override
+//! the methods for the nodes they are interested in, calling the `walk_` function from the
+//! override to continue into the children. [`MutVisitor`](trait.MutVisitor.html) does the same
+//! with mutable access to each node.
+//!
+//! ```
+//! use rst::ast::Paragraph;
+//! use rst::location::TextSource;
+//! use rst::visit::{self, Visitor};
+//! use rst::{Parser, TokenStream};
+//!
+//! #[derive(Default)]
+//! struct Paragraphs(usize);
+//!
+//! impl Visitor for Paragraphs {
+//!     fn visit_paragraph(&mut self, paragraph: &Paragraph) {
+//!         self.0 += 1;
+//!         visit::walk_paragraph(self, paragraph);
+//!     }
+//! }
+//!
+//! let mut source = TextSource::from_str("example", "One.\n\n- Two.\n- Three.\n");
+//! let document = Parser::new(TokenStream::try_new(&mut source)?).parse()?;
+//!
+//! let mut paragraphs = Paragraphs::default();
+//! paragraphs.visit_document(&document);
+//! assert_eq!(paragraphs.0, 3);
+//! # Ok::<(), rst::ParseError>(())
+//! ```
+
+use crate::ast::*;
+
+/// Traverse a syntax tree by reference.
+pub trait Visitor {
+    fn visit_document(&mut self, document: &Document) {
+        walk_document(self, document)
+    }
+
+    fn visit_section(&mut self, section: &Section) {
+        walk_section(self, section)
+    }
+
+    fn visit_transition(&mut self) {}
+
+    fn visit_body(&mut self, body: &Body) {
+        walk_body(self, body)
+    }
+
+    /// Visit any kind of block, which by default visits the block with its own method.
+    fn visit_block(&mut self, block: &BodyBlock) {
+        walk_block(self, block)
+    }
+
+    fn visit_paragraph(&mut self, paragraph: &Paragraph) {
+        walk_paragraph(self, paragraph)
+    }
+
+    fn visit_list(&mut self, list: &List) {
+        walk_list(self, list)
+    }
+
+    fn visit_definition_list(&mut self, list: &DefinitionList) {
+        walk_definition_list(self, list)
+    }
+
+    fn visit_field_list(&mut self, list: &FieldList) {
+        walk_field_list(self, list)
+    }
+
+    fn visit_option_list(&mut self, list: &OptionList) {
+        walk_option_list(self, list)
+    }
+
+    fn visit_literal_block(&mut self, _literal: &LiteralBlock) {}
+
+    fn visit_line_block(&mut self, block: &LineBlock) {
+        walk_line_block(self, block)
+    }
+
+    fn visit_block_quote(&mut self, quote: &BlockQuote) {
+        walk_block_quote(self, quote)
+    }
+
+    fn visit_doctest(&mut self, _doctest: &DocTest) {}
+
+    fn visit_table(&mut self, table: &Table) {
+        walk_table(self, table)
+    }
+
+    fn visit_footnote(&mut self, footnote: &Footnote) {
+        walk_footnote(self, footnote)
+    }
+
+    fn visit_citation(&mut self, citation: &Citation) {
+        walk_citation(self, citation)
+    }
+
+    fn visit_target(&mut self, _target: &Target) {}
+
+    fn visit_directive(&mut self, directive: &Directive) {
+        walk_directive(self, directive)
+    }
+
+    fn visit_substitution(&mut self, substitution: &Substitution) {
+        walk_substitution(self, substitution)
+    }
+
+    fn visit_comment(&mut self, _comment: &Comment) {}
+
+    fn visit_text(&mut self, text: &Text) {
+        walk_text(self, text)
+    }
+
+    fn visit_inline(&mut self, inline: &Inline) {
+        walk_inline(self, inline)
+    }
+}
+
+pub fn walk_document<V: Visitor + ?Sized>(visitor: &mut V, document: &Document) {
+    walk_children(visitor, &document.0)
+}
+
+pub fn walk_section<V: Visitor + ?Sized>(visitor: &mut V, section: &Section) {
+    walk_children(visitor, &section.children)
+}
+
+fn walk_children<V: Visitor + ?Sized>(visitor: &mut V, children: &[SectionChildren]) {
+    for child in children {
+        match child {
+            SectionChildren::Body(block) => visitor.visit_block(block),
+            SectionChildren::Transition => visitor.visit_transition(),
+            SectionChildren::Section(section) => visitor.visit_section(section),
+        }
+    }
+}
+
+pub fn walk_body<V: Visitor + ?Sized>(visitor: &mut V, body: &Body) {
+    for block in &body.0 {
+        visitor.visit_block(block);
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &BodyBlock) {
+    match block {
+        BodyBlock::Paragraph(paragraph) => visitor.visit_paragraph(paragraph),
+        BodyBlock::List(list) => visitor.visit_list(list),
+        BodyBlock::DefinitionList(list) => visitor.visit_definition_list(list),
+        BodyBlock::FieldList(list) => visitor.visit_field_list(list),
+        BodyBlock::OptionList(list) => visitor.visit_option_list(list),
+        BodyBlock::LiteralBlock(literal) => visitor.visit_literal_block(literal),
+        BodyBlock::LineBlock(block) => visitor.visit_line_block(block),
+        BodyBlock::BlockQuote(quote) => visitor.visit_block_quote(quote),
+        BodyBlock::DocTest(doctest) => visitor.visit_doctest(doctest),
+        BodyBlock::Table(table) => visitor.visit_table(table),
+        BodyBlock::Footnote(footnote) => visitor.visit_footnote(footnote),
+        BodyBlock::Citation(citation) => visitor.visit_citation(citation),
+        BodyBlock::Target(target) => visitor.visit_target(target),
+        BodyBlock::Directive(directive) => visitor.visit_directive(directive),
+        BodyBlock::Substitution(substitution) => visitor.visit_substitution(substitution),
+        BodyBlock::Comment(comment) => visitor.visit_comment(comment),
+    }
+}
+
+pub fn walk_paragraph<V: Visitor + ?Sized>(visitor: &mut V, paragraph: &Paragraph) {
+    visitor.visit_text(&paragraph.0)
+}
+
+pub fn walk_list<V: Visitor + ?Sized>(visitor: &mut V, list: &List) {
+    for element in &list.elements {
+        visitor.visit_body(element);
+    }
+}
+
+pub fn walk_definition_list<V: Visitor + ?Sized>(visitor: &mut V, list: &DefinitionList) {
+    for definition in &list.0 {
+        visitor.visit_text(&definition.term);
+        for classifier in &definition.classifiers {
+            visitor.visit_text(classifier);
+        }
+        visitor.visit_body(&definition.definition);
+    }
+}
+
+pub fn walk_field_list<V: Visitor + ?Sized>(visitor: &mut V, list: &FieldList) {
+    for field in &list.0 {
+        visitor.visit_text(&field.marker);
+        visitor.visit_body(&field.body);
+    }
+}
+
+pub fn walk_option_list<V: Visitor + ?Sized>(visitor: &mut V, list: &OptionList) {
+    for item in &list.0 {
+        visitor.visit_text(&item.description);
+    }
+}
+
+pub fn walk_line_block<V: Visitor + ?Sized>(visitor: &mut V, block: &LineBlock) {
+    fn walk_lines<V: Visitor + ?Sized>(visitor: &mut V, lines: &[Line]) {
+        for line in lines {
+            visitor.visit_text(&line.content);
+            walk_lines(visitor, &line.children);
+        }
+    }
+
+    walk_lines(visitor, &block.0)
+}
+
+pub fn walk_block_quote<V: Visitor + ?Sized>(visitor: &mut V, quote: &BlockQuote) {
+    visitor.visit_body(&quote.quote);
+    if let Some(attribution) = &quote.attribution {
+        visitor.visit_text(attribution);
+    }
+}
+
+pub fn walk_table<V: Visitor + ?Sized>(visitor: &mut V, table: &Table) {
+    for Row(cells) in table.header.iter().chain(&table.body) {
+        for cell in cells {
+            visitor.visit_body(&cell.content);
+        }
+    }
+}
+
+pub fn walk_footnote<V: Visitor + ?Sized>(visitor: &mut V, footnote: &Footnote) {
+    visitor.visit_body(&footnote.body)
+}
+
+pub fn walk_citation<V: Visitor + ?Sized>(visitor: &mut V, citation: &Citation) {
+    visitor.visit_body(&citation.body)
+}
+
+pub fn walk_directive<V: Visitor + ?Sized>(visitor: &mut V, directive: &Directive) {
+    if !directive.fields.0.is_empty() {
+        visitor.visit_field_list(&directive.fields);
+    }
+    if let DirectiveContent::Parsed(body) = &directive.content {
+        visitor.visit_body(body);
+    }
+}
+
+pub fn walk_substitution<V: Visitor + ?Sized>(visitor: &mut V, substitution: &Substitution) {
+    visitor.visit_directive(&substitution.directive)
+}
+
+pub fn walk_text<V: Visitor + ?Sized>(visitor: &mut V, text: &Text) {
+    for inline in &text.0 {
+        visitor.visit_inline(inline);
+    }
+}
+
+pub fn walk_inline<V: Visitor + ?Sized>(visitor: &mut V, inline: &Inline) {
+    match inline {
+        Inline::Emphasis(Emphasis(text)) | Inline::Strong(Strong(text)) => visitor.visit_text(text),
+        Inline::HyperlinkReference(reference) => visitor.visit_text(&reference.label),
+        Inline::Target(InlineInternalTarget(text)) => visitor.visit_text(text),
+        _ => {}
+    }
+}
+
+/// Traverse a syntax tree, modifying it in place.
+pub trait MutVisitor {
+    fn visit_document(&mut self, document: &mut Document) {
+        walk_document_mut(self, document)
+    }
+
+    fn visit_section(&mut self, section: &mut Section) {
+        walk_section_mut(self, section)
+    }
+
+    fn visit_transition(&mut self) {}
+
+    fn visit_body(&mut self, body: &mut Body) {
+        walk_body_mut(self, body)
+    }
+
+    /// Visit any kind of block, which by default visits the block with its own method.
+    fn visit_block(&mut self, block: &mut BodyBlock) {
+        walk_block_mut(self, block)
+    }
+
+    fn visit_paragraph(&mut self, paragraph: &mut Paragraph) {
+        walk_paragraph_mut(self, paragraph)
+    }
+
+    fn visit_list(&mut self, list: &mut List) {
+        walk_list_mut(self, list)
+    }
+
+    fn visit_definition_list(&mut self, list: &mut DefinitionList) {
+        walk_definition_list_mut(self, list)
+    }
+
+    fn visit_field_list(&mut self, list: &mut FieldList) {
+        walk_field_list_mut(self, list)
+    }
+
+    fn visit_option_list(&mut self, list: &mut OptionList) {
+        walk_option_list_mut(self, list)
+    }
+
+    fn visit_literal_block(&mut self, _literal: &mut LiteralBlock) {}
+
+    fn visit_line_block(&mut self, block: &mut LineBlock) {
+        walk_line_block_mut(self, block)
+    }
+
+    fn visit_block_quote(&mut self, quote: &mut BlockQuote) {
+        walk_block_quote_mut(self, quote)
+    }
+
+    fn visit_doctest(&mut self, _doctest: &mut DocTest) {}
+
+    fn visit_table(&mut self, table: &mut Table) {
+        walk_table_mut(self, table)
+    }
+
+    fn visit_footnote(&mut self, footnote: &mut Footnote) {
+        walk_footnote_mut(self, footnote)
+    }
+
+    fn visit_citation(&mut self, citation: &mut Citation) {
+        walk_citation_mut(self, citation)
+    }
+
+    fn visit_target(&mut self, _target: &mut Target) {}
+
+    fn visit_directive(&mut self, directive: &mut Directive) {
+        walk_directive_mut(self, directive)
+    }
+
+    fn visit_substitution(&mut self, substitution: &mut Substitution) {
+        walk_substitution_mut(self, substitution)
+    }
+
+    fn visit_comment(&mut self, _comment: &mut Comment) {}
+
+    fn visit_text(&mut self, text: &mut Text) {
+        walk_text_mut(self, text)
+    }
+
+    fn visit_inline(&mut self, inline: &mut Inline) {
+        walk_inline_mut(self, inline)
+    }
+}
+
+pub fn walk_document_mut<V: MutVisitor + ?Sized>(visitor: &mut V, document: &mut Document) {
+    walk_children_mut(visitor, &mut document.0)
+}
+
+pub fn walk_section_mut<V: MutVisitor + ?Sized>(visitor: &mut V, section: &mut Section) {
+    walk_children_mut(visitor, &mut section.children)
+}
+
+fn walk_children_mut<V: MutVisitor + ?Sized>(visitor: &mut V, children: &mut [SectionChildren]) {
+    for child in children {
+        match child {
+            SectionChildren::Body(block) => visitor.visit_block(block),
+            SectionChildren::Transition => visitor.visit_transition(),
+            SectionChildren::Section(section) => visitor.visit_section(section),
+        }
+    }
+}
+
+pub fn walk_body_mut<V: MutVisitor + ?Sized>(visitor: &mut V, body: &mut Body) {
+    for block in &mut body.0 {
+        visitor.visit_block(block);
+    }
+}
+
+pub fn walk_block_mut<V: MutVisitor + ?Sized>(visitor: &mut V, block: &mut BodyBlock) {
+    match block {
+        BodyBlock::Paragraph(paragraph) => visitor.visit_paragraph(paragraph),
+        BodyBlock::List(list) => visitor.visit_list(list),
+        BodyBlock::DefinitionList(list) => visitor.visit_definition_list(list),
+        BodyBlock::FieldList(list) => visitor.visit_field_list(list),
+        BodyBlock::OptionList(list) => visitor.visit_option_list(list),
+        BodyBlock::LiteralBlock(literal) => visitor.visit_literal_block(literal),
+        BodyBlock::LineBlock(block) => visitor.visit_line_block(block),
+        BodyBlock::BlockQuote(quote) => visitor.visit_block_quote(quote),
+        BodyBlock::DocTest(doctest) => visitor.visit_doctest(doctest),
+        BodyBlock::Table(table) => visitor.visit_table(table),
+        BodyBlock::Footnote(footnote) => visitor.visit_footnote(footnote),
+        BodyBlock::Citation(citation) => visitor.visit_citation(citation),
+        BodyBlock::Target(target) => visitor.visit_target(target),
+        BodyBlock::Directive(directive) => visitor.visit_directive(directive),
+        BodyBlock::Substitution(substitution) => visitor.visit_substitution(substitution),
+        BodyBlock::Comment(comment) => visitor.visit_comment(comment),
+    }
+}
+
+pub fn walk_paragraph_mut<V: MutVisitor + ?Sized>(visitor: &mut V, paragraph: &mut Paragraph) {
+    visitor.visit_text(&mut paragraph.0)
+}
+
+pub fn walk_list_mut<V: MutVisitor + ?Sized>(visitor: &mut V, list: &mut List) {
+    for element in &mut list.elements {
+        visitor.visit_body(element);
+    }
+}
+
+pub fn walk_definition_list_mut<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    list: &mut DefinitionList,
+) {
+    for definition in &mut list.0 {
+        visitor.visit_text(&mut definition.term);
+        for classifier in &mut definition.classifiers {
+            visitor.visit_text(classifier);
+        }
+        visitor.visit_body(&mut definition.definition);
+    }
+}
+
+pub fn walk_field_list_mut<V: MutVisitor + ?Sized>(visitor: &mut V, list: &mut FieldList) {
+    for field in &mut list.0 {
+        visitor.visit_text(&mut field.marker);
+        visitor.visit_body(&mut field.body);
+    }
+}
+
+pub fn walk_option_list_mut<V: MutVisitor + ?Sized>(visitor: &mut V, list: &mut OptionList) {
+    for item in &mut list.0 {
+        visitor.visit_text(&mut item.description);
+    }
+}
+
+pub fn walk_line_block_mut<V: MutVisitor + ?Sized>(visitor: &mut V, block: &mut LineBlock) {
+    fn walk_lines<V: MutVisitor + ?Sized>(visitor: &mut V, lines: &mut [Line]) {
+        for line in lines {
+            visitor.visit_text(&mut line.content);
+            walk_lines(visitor, &mut line.children);
+        }
+    }
+
+    walk_lines(visitor, &mut block.0)
+}
+
+pub fn walk_block_quote_mut<V: MutVisitor + ?Sized>(visitor: &mut V, quote: &mut BlockQuote) {
+    visitor.visit_body(&mut quote.quote);
+    if let Some(attribution) = &mut quote.attribution {
+        visitor.visit_text(attribution);
+    }
+}
+
+pub fn walk_table_mut<V: MutVisitor + ?Sized>(visitor: &mut V, table: &mut Table) {
+    for Row(cells) in table.header.iter_mut().chain(&mut table.body) {
+        for cell in cells {
+            visitor.visit_body(&mut cell.content);
+        }
+    }
+}
+
+pub fn walk_footnote_mut<V: MutVisitor + ?Sized>(visitor: &mut V, footnote: &mut Footnote) {
+    visitor.visit_body(&mut footnote.body)
+}
+
+pub fn walk_citation_mut<V: MutVisitor + ?Sized>(visitor: &mut V, citation: &mut Citation) {
+    visitor.visit_body(&mut citation.body)
+}
+
+pub fn walk_directive_mut<V: MutVisitor + ?Sized>(visitor: &mut V, directive: &mut Directive) {
+    if !directive.fields.0.is_empty() {
+        visitor.visit_field_list(&mut directive.fields);
+    }
+    if let DirectiveContent::Parsed(body) = &mut directive.content {
+        visitor.visit_body(body);
+    }
+}
+
+pub fn walk_substitution_mut<V: MutVisitor + ?Sized>(
+    visitor: &mut V,
+    substitution: &mut Substitution,
+) {
+    visitor.visit_directive(&mut substitution.directive)
+}
+
+pub fn walk_text_mut<V: MutVisitor + ?Sized>(visitor: &mut V, text: &mut Text) {
+    for inline in &mut text.0 {
+        visitor.visit_inline(inline);
+    }
+}
+
+pub fn walk_inline_mut<V: MutVisitor + ?Sized>(visitor: &mut V, inline: &mut Inline) {
+    match inline {
+        Inline::Emphasis(Emphasis(text)) | Inline::Strong(Strong(text)) => visitor.visit_text(text),
+        Inline::HyperlinkReference(reference) => visitor.visit_text(&mut reference.label),
+        Inline::Target(InlineInternalTarget(text)) => visitor.visit_text(text),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::TextSource;
+    use crate::{Parser, TokenStream};
+
+    const DOCUMENT: &str = "\
+Title
+=====
+
+Intro paragraph.
+
+- Item one.
+- Item two.
+
+----
+
+Section
+-------
+
+  Quoted text.
+
+  -- Someone
+
+.. [1] Footnote text.
+";
+
+    fn parse(text: &str) -> Document {
+        let mut source = TextSource::from_str("visit", text);
+        Parser::new(TokenStream::try_new(&mut source).unwrap())
+            .parse()
+            .unwrap()
+    }
+
+    /// Records the kinds of node visited, in order.
+    #[derive(Default)]
+    struct Trace(Vec<String>);
+
+    impl Visitor for Trace {
+        fn visit_section(&mut self, section: &Section) {
+            self.0.push(format!("section {}", section.title));
+            walk_section(self, section);
+        }
+
+        fn visit_transition(&mut self) {
+            self.0.push("transition".to_owned());
+        }
+
+        fn visit_list(&mut self, list: &List) {
+            self.0.push("list".to_owned());
+            walk_list(self, list);
+        }
+
+        fn visit_block_quote(&mut self, quote: &BlockQuote) {
+            self.0.push("block_quote".to_owned());
+            walk_block_quote(self, quote);
+        }
+
+        fn visit_footnote(&mut self, footnote: &Footnote) {
+            self.0.push("footnote".to_owned());
+            walk_footnote(self, footnote);
+        }
+
+        fn visit_text(&mut self, text: &Text) {
+            self.0.push(text.plain_text());
+        }
+    }
+
+    #[test]
+    fn visits_in_document_order() {
+        let mut trace = Trace::default();
+        trace.visit_document(&parse(DOCUMENT));
+        assert_eq!(
+            trace.0,
+            &[
+                "section Title",
+                "Intro paragraph.",
+                "list",
+                "Item one.",
+                "Item two.",
+                "transition",
+                "section Section",
+                "block_quote",
+                "Quoted text.",
+                "Someone",
+                "footnote",
+                "Footnote text.",
+            ]
+        );
+    }
+
+    #[test]
+    fn overridden_methods_stop_traversal() {
+        struct Words(usize);
+
+        impl Visitor for Words {
+            fn visit_inline(&mut self, inline: &Inline) {
+                if let Inline::Word(_) = inline {
+                    self.0 += 1;
+                }
+            }
+
+            fn visit_footnote(&mut self, _footnote: &Footnote) {}
+        }
+
+        let mut words = Words(0);
+        words.visit_document(&parse(DOCUMENT));
+        assert_eq!(words.0, 9);
+    }
+
+    #[test]
+    fn mutable_visits() {
+        struct Shout;
+
+        impl MutVisitor for Shout {
+            fn visit_inline(&mut self, inline: &mut Inline) {
+                match inline {
+                    Inline::Word(word) => *word = word.to_uppercase(),
+                    inline => walk_inline_mut(self, inline),
+                }
+            }
+        }
+
+        let mut document = parse(DOCUMENT);
+        Shout.visit_document(&mut document);
+
+        let mut trace = Trace::default();
+        trace.visit_document(&document);
+        assert_eq!(trace.0[1], "INTRO PARAGRAPH.");
+        assert_eq!(trace.0[9], "SOMEONE");
+        assert_eq!(trace.0[0], "section Title");
+    }
+}
@@ -0,0 +1,7 @@
+//! Writers that render a document in other formats.
+
+mod html;
+mod rst;
+
+pub use self::html::{HtmlOptions, HtmlWriter};
+pub use self::rst::RstWriter;
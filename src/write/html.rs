@@ -0,0 +1,784 @@
+//! Rendering of a document as HTML.
+//!
+//! The markup follows the HTML5 writer of docutils, so that stylesheets written for docutils
+//! output can be reused.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::ast::*;
+use crate::names::{make_id, normalize_name, IdGenerator, IdStyle};
+
+/// Options controlling the HTML written by an [`HtmlWriter`](struct.HtmlWriter.html).
+#[derive(Debug, Clone, Default)]
+pub struct HtmlOptions {
+    /// Write a complete page, rather than only the body of the document.
+    pub standalone: bool,
+    /// Highlight the syntax of code.
+    ///
+    /// This is reserved for future use and currently has no effect.
+    pub syntax_highlight: bool,
+    /// Prepended to the id of each section.
+    pub section_id_prefix: String,
+}
+
+/// Writes a document as HTML.
+///
+/// ```
+/// use rst::ast::Document;
+/// use rst::write::HtmlWriter;
+///
+/// let document: Document = "Title\n=====\n\nSome text & more.\n".parse()?;
+///
+/// let mut output = Vec::new();
+/// HtmlWriter::default().write_document(&document, &mut output)?;
+/// assert_eq!(
+///     String::from_utf8(output)?,
+///     "<section id=\"title\">\n<h1>Title</h1>\n<p>Some text &amp; more.</p>\n</section>\n",
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HtmlWriter {
+    options: HtmlOptions,
+}
+
+impl HtmlWriter {
+    pub fn new(options: HtmlOptions) -> Self {
+        HtmlWriter { options }
+    }
+
+    /// Write the document.
+    pub fn write_document(&self, document: &Document, out: &mut impl Write) -> io::Result<()> {
+        // References may precede their targets, so the document is rendered once to find the id
+        // of each target before it is rendered again with the references resolved.
+        let mut targets = HtmlRenderer::new(&self.options, HashMap::new());
+        targets.children(&document.0, 1);
+        let mut renderer = HtmlRenderer::new(&self.options, targets.targets);
+
+        if self.options.standalone {
+            let title = document.0.iter().find_map(|child| match child {
+                SectionChildren::Section(section) => Some(section.title.as_str()),
+                _ => None,
+            });
+            renderer.line("<!DOCTYPE html>");
+            renderer.line("<html>");
+            renderer.line("<head>");
+            renderer.line("<meta charset=\"utf-8\">");
+            if let Some(title) = title {
+                renderer.line(&format!("<title>{}</title>", escape(title)));
+            }
+            renderer.line("</head>");
+            renderer.line("<body>");
+            renderer.line("<main>");
+            renderer.children(&document.0, 1);
+            renderer.line("</main>");
+            renderer.line("</body>");
+            renderer.line("</html>");
+        } else {
+            renderer.children(&document.0, 1);
+        }
+
+        out.write_all(renderer.output.as_bytes())
+    }
+}
+
+struct HtmlRenderer<'o> {
+    options: &'o HtmlOptions,
+    ids: IdGenerator,
+    output: String,
+    /// The number of automatically numbered footnotes so far.
+    numbers: usize,
+    /// The number of auto-symbol footnotes so far.
+    symbols: usize,
+    /// The id of each internal target, by normalised name.
+    targets: HashMap<String, String>,
+}
+
+impl<'o> HtmlRenderer<'o> {
+    fn new(options: &'o HtmlOptions, targets: HashMap<String, String>) -> Self {
+        HtmlRenderer {
+            options,
+            ids: IdGenerator::new(IdStyle::Docutils),
+            output: String::new(),
+            numbers: 0,
+            symbols: 0,
+            targets,
+        }
+    }
+
+    /// Generate the id of a target with the given name, to which internal references will link.
+    fn target_id(&mut self, name: &str, prefix: &str) -> String {
+        let id = format!("{}{}", prefix, self.ids.id_for(name));
+        self.targets
+            .entry(normalize_name(name))
+            .or_insert_with(|| id.clone());
+        id
+    }
+
+    /// The id of the target an internal reference with the given name links to.
+    fn reference_id(&self, name: &str) -> String {
+        match self.targets.get(&normalize_name(name)) {
+            Some(id) => id.clone(),
+            None => make_id(name),
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        self.output.push_str(line);
+        self.output.push('\n');
+    }
+
+    fn children(&mut self, children: &[SectionChildren], level: usize) {
+        for child in children {
+            match child {
+                SectionChildren::Body(block) => self.block(block),
+                SectionChildren::Transition => self.line("<hr class=\"docutils\" />"),
+                SectionChildren::Section(section) => self.section(section, level),
+            }
+        }
+    }
+
+    fn section(&mut self, section: &Section, level: usize) {
+        let id = self.target_id(&section.title, &self.options.section_id_prefix);
+        let heading = level.min(6);
+        self.line(&format!("<section id=\"{}\">", escape(&id)));
+        self.line(&format!(
+            "<h{}>{}</h{}>",
+            heading,
+            escape(&section.title),
+            heading
+        ));
+        self.children(&section.children, level + 1);
+        self.line("</section>");
+    }
+
+    fn body(&mut self, body: &Body) {
+        for block in &body.0 {
+            self.block(block);
+        }
+    }
+
+    fn block(&mut self, block: &BodyBlock) {
+        match block {
+            BodyBlock::Paragraph(Paragraph(text)) => {
+                let text = self.inline_html(text);
+                self.line(&format!("<p>{}</p>", text));
+            }
+            BodyBlock::List(list) => self.list(list),
+            BodyBlock::DefinitionList(DefinitionList(definitions)) => {
+                self.line("<dl>");
+                for definition in definitions {
+                    let mut term = self.inline_html(&definition.term);
+                    for classifier in &definition.classifiers {
+                        let classifier = self.inline_html(classifier);
+                        term.push_str(&format!(
+                            "<span class=\"classifier-delimiter\">:</span> \
+                             <span class=\"classifier\">{}</span>",
+                            classifier
+                        ));
+                    }
+                    self.line(&format!("<dt>{}</dt>", term));
+                    self.line("<dd>");
+                    self.body(&definition.definition);
+                    self.line("</dd>");
+                }
+                self.line("</dl>");
+            }
+            BodyBlock::FieldList(fields) => self.field_list(fields),
+            BodyBlock::OptionList(OptionList(items)) => {
+                self.line("<dl class=\"option-list\">");
+                for item in items {
+                    let options: Vec<_> = item
+                        .options
+                        .iter()
+                        .map(|(option, argument)| match argument {
+                            Some(argument) => format!(
+                                "<span class=\"option\">{} <var>{}</var></span>",
+                                escape(option),
+                                escape(argument)
+                            ),
+                            None => format!("<span class=\"option\">{}</span>", escape(option)),
+                        })
+                        .collect();
+                    self.line(&format!("<dt><kbd>{}</kbd></dt>", options.join(", ")));
+                    let description = self.inline_html(&item.description);
+                    self.line(&format!("<dd><p>{}</p></dd>", description));
+                }
+                self.line("</dl>");
+            }
+            BodyBlock::LiteralBlock(LiteralBlock(literal)) => {
+                self.line(&format!("<pre><code>{}</code></pre>", escape(literal)));
+            }
+            BodyBlock::LineBlock(LineBlock(lines)) => self.line_block(lines),
+            BodyBlock::BlockQuote(quote) => {
+                self.line("<blockquote>");
+                self.body(&quote.quote);
+                if let Some(attribution) = &quote.attribution {
+                    let attribution = self.inline_html(attribution);
+                    self.line(&format!("<p class=\"attribution\">—{}</p>", attribution));
+                }
+                self.line("</blockquote>");
+            }
+            BodyBlock::DocTest(DocTest(doctest)) => {
+                self.line(&format!(
+                    "<pre class=\"code python doctest\">{}</pre>",
+                    escape(doctest)
+                ));
+            }
+            BodyBlock::Table(table) => self.table(table),
+            BodyBlock::Footnote(footnote) => {
+                let identifier = &footnote.identifier;
+                let id = match identifier {
+                    FootnoteIdentifier::Labelled(label) => self.target_id(label, ""),
+                    _ => self.ids.auto_id(),
+                };
+                let counter = match identifier {
+                    FootnoteIdentifier::Numbered(_) => None,
+                    FootnoteIdentifier::AutoSymbol => Some(&mut self.symbols),
+                    _ => Some(&mut self.numbers),
+                };
+                let label = match counter {
+                    Some(counter) => {
+                        *counter += 1;
+                        identifier.label(*counter - 1)
+                    }
+                    None => identifier.label(0),
+                };
+                self.line(&format!(
+                    "<aside class=\"footnote\" id=\"{}\">",
+                    escape(&id)
+                ));
+                self.line(&format!(
+                    "<span class=\"label\">[{}]</span>",
+                    escape(&label)
+                ));
+                self.body(&footnote.body);
+                self.line("</aside>");
+            }
+            BodyBlock::Citation(citation) => {
+                let id = self.target_id(&citation.name, "");
+                self.line(&format!("<div class=\"citation\" id=\"{}\">", escape(&id)));
+                self.line(&format!(
+                    "<span class=\"label\">[{}]</span>",
+                    escape(&citation.name)
+                ));
+                self.body(&citation.body);
+                self.line("</div>");
+            }
+            // Targets and substitution definitions are resolved rather than rendered.
+            BodyBlock::Target(_) | BodyBlock::Substitution(_) => {}
+            BodyBlock::Directive(directive) => {
+                self.line(&format!(
+                    "<div class=\"{}\">",
+                    escape(&make_id(&directive.marker))
+                ));
+                match &directive.content {
+                    DirectiveContent::Literal(literal) if literal.is_empty() => {}
+                    DirectiveContent::Literal(literal) => {
+                        self.line(&format!("<pre>{}</pre>", escape(literal)));
+                    }
+                    DirectiveContent::Parsed(body) => self.body(body),
+                }
+                self.line("</div>");
+            }
+            BodyBlock::Comment(Comment(comment)) => {
+                self.line(&format!("<!-- {} -->", comment_text(comment)));
+            }
+        }
+    }
+
+    fn list(&mut self, list: &List) {
+        let (start, class) = match list.marker {
+            ListMarker::Bullet => {
+                self.line("<ul>");
+                for element in &list.elements {
+                    self.list_item(element);
+                }
+                self.line("</ul>");
+                return;
+            }
+            ListMarker::Arabic(start) => (start, "arabic"),
+            ListMarker::LatinUppercase(start) => (start, "upperalpha"),
+            ListMarker::LatinLowercase(start) => (start, "loweralpha"),
+            ListMarker::RomanUppercase(start) => (start, "upperroman"),
+            ListMarker::RomanLowercase(start) => (start, "lowerroman"),
+            ListMarker::AutoNumbered => (1, "arabic"),
+            ListMarker::AutoSymbol => (1, "symbol"),
+        };
+
+        match start {
+            1 => self.line(&format!("<ol class=\"{}\">", class)),
+            start => self.line(&format!("<ol class=\"{}\" start=\"{}\">", class, start)),
+        }
+        for element in &list.elements {
+            self.list_item(element);
+        }
+        self.line("</ol>");
+    }
+
+    fn list_item(&mut self, element: &Body) {
+        self.line("<li>");
+        self.body(element);
+        self.line("</li>");
+    }
+
+    fn field_list(&mut self, FieldList(fields): &FieldList) {
+        self.line("<dl class=\"field-list\">");
+        for field in fields {
+            let marker = self.inline_html(&field.marker);
+            self.line(&format!("<dt>{}</dt>", marker));
+            self.line("<dd>");
+            self.body(&field.body);
+            self.line("</dd>");
+        }
+        self.line("</dl>");
+    }
+
+    fn line_block(&mut self, lines: &[Line]) {
+        self.line("<div class=\"line-block\">");
+        for line in lines {
+            let content = self.inline_html(&line.content);
+            self.line(&format!("<div class=\"line\">{}</div>", content));
+            if !line.children.is_empty() {
+                self.line_block(&line.children);
+            }
+        }
+        self.line("</div>");
+    }
+
+    fn table(&mut self, table: &Table) {
+        self.line("<table>");
+        if !table.header.is_empty() {
+            self.line("<thead>");
+            for row in &table.header {
+                self.row("th", row);
+            }
+            self.line("</thead>");
+        }
+        self.line("<tbody>");
+        for row in &table.body {
+            self.row("td", row);
+        }
+        self.line("</tbody>");
+        self.line("</table>");
+    }
+
+    fn row(&mut self, tag: &str, Row(cells): &Row) {
+        self.line("<tr>");
+        for cell in cells.iter().filter(|cell| !cell.is_covered()) {
+            let mut attributes = String::new();
+            if cell.column_span > 1 {
+                attributes.push_str(&format!(" colspan=\"{}\"", cell.column_span));
+            }
+            if cell.row_span > 1 {
+                attributes.push_str(&format!(" rowspan=\"{}\"", cell.row_span));
+            }
+            match &cell.content.0[..] {
+                // A cell holding no more than a paragraph is written without the paragraph.
+                [] => self.line(&format!("<{}{}></{}>", tag, attributes, tag)),
+                [BodyBlock::Paragraph(Paragraph(text))] => {
+                    let content = self.inline_html(text);
+                    self.line(&format!("<{}{}>{}</{}>", tag, attributes, content, tag));
+                }
+                _ => {
+                    self.line(&format!("<{}{}>", tag, attributes));
+                    self.body(&cell.content);
+                    self.line(&format!("</{}>", tag));
+                }
+            }
+        }
+        self.line("</tr>");
+    }
+
+    /// Render inline text as HTML.
+    fn inline_html(&mut self, text: &Text) -> String {
+        let mut html = String::new();
+        for inline in &text.0 {
+            self.push_inline(&mut html, inline);
+        }
+        html
+    }
+
+    fn push_inline(&mut self, html: &mut String, inline: &Inline) {
+        match inline {
+            Inline::Emphasis(Emphasis(text)) => {
+                html.push_str(&format!("<em>{}</em>", self.inline_html(text)));
+            }
+            Inline::Strong(Strong(text)) => {
+                html.push_str(&format!("<strong>{}</strong>", self.inline_html(text)));
+            }
+            Inline::Interpreted(interpreted) => match &interpreted.role {
+                Some(role) => html.push_str(&format!(
+                    "<span class=\"{}\">{}</span>",
+                    escape(role),
+                    escape(&interpreted.text)
+                )),
+                None => html.push_str(&format!("<cite>{}</cite>", escape(&interpreted.text))),
+            },
+            Inline::Literal(Literal(literal)) => {
+                html.push_str(&format!("<code>{}</code>", escape(literal)));
+            }
+            Inline::HyperlinkReference(reference) => {
+                let label = self.inline_html(&reference.label);
+                match &reference.target {
+                    Some(target) => html.push_str(&format!(
+                        "<a class=\"reference external\" href=\"{}\">{}</a>",
+                        escape(target),
+                        label
+                    )),
+                    None => html.push_str(&format!(
+                        "<a class=\"reference internal\" href=\"#{}\">{}</a>",
+                        escape(&self.reference_id(&reference.label.plain_text())),
+                        label
+                    )),
+                }
+            }
+            Inline::Target(InlineInternalTarget(text)) => {
+                let id = self.target_id(&text.plain_text(), "");
+                html.push_str(&format!(
+                    "<span class=\"target\" id=\"{}\">{}</span>",
+                    escape(&id),
+                    self.inline_html(text)
+                ));
+            }
+            Inline::StandaloneHyperlink(StandaloneHyperlink(url)) => html.push_str(&format!(
+                "<a class=\"reference external\" href=\"{}\">{}</a>",
+                escape(url.as_str()),
+                escape(url.as_str())
+            )),
+            Inline::Unit(unit) => html.push_str(&unit.to_string()),
+            Inline::Word(word) => html.push_str(&escape(word)),
+            Inline::Character(c) => html.push_str(&escape(&c.to_string())),
+            Inline::Whitespace => html.push(' '),
+        }
+    }
+}
+
+/// Escape text for use in HTML content and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Make text safe for use within an HTML comment.
+///
+/// Comment text may not contain `--`, so a space is placed between adjacent dashes. The spaces
+/// written around the text keep it from starting with `>` or `->` or ending in `-` or `<!-`.
+fn comment_text(text: &str) -> String {
+    let mut safe = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '-' && safe.ends_with('-') {
+            safe.push(' ');
+        }
+        safe.push(c);
+    }
+    safe
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::TextSource;
+    use crate::{Parser, TokenStream};
+
+    fn render(text: &str, options: HtmlOptions) -> String {
+        let mut source = TextSource::from_str("html", text);
+        let document = Parser::new(TokenStream::try_new(&mut source).unwrap())
+            .parse()
+            .unwrap();
+        let mut output = Vec::new();
+        HtmlWriter::new(options)
+            .write_document(&document, &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn blocks() {
+        let text = concat!(
+            "Install with pip & <name>.\n",
+            "\n",
+            "* One & two.\n",
+            "* Three.\n",
+            "\n",
+            "Quoted:\n",
+            "\n",
+            "    3. Quoted list.\n",
+            "\n",
+            "    -- Author\n",
+            "\n",
+            "Code::\n",
+            "\n",
+            "    if a < b:\n",
+            "        pass\n",
+            "\n",
+            "------------\n",
+            "\n",
+            ".. [#note] A footnote.\n",
+            ".. [*] A symbol.\n",
+            ".. [*] Another.\n",
+            ".. A comment -- with dashes.\n",
+        );
+
+        assert_eq!(
+            render(text, HtmlOptions::default()),
+            concat!(
+                "<p>Install with pip &amp; &lt;name&gt;.</p>\n",
+                "<ul>\n",
+                "<li>\n",
+                "<p>One &amp; two.</p>\n",
+                "</li>\n",
+                "<li>\n",
+                "<p>Three.</p>\n",
+                "</li>\n",
+                "</ul>\n",
+                "<p>Quoted:</p>\n",
+                "<blockquote>\n",
+                "<ol class=\"arabic\" start=\"3\">\n",
+                "<li>\n",
+                "<p>Quoted list.</p>\n",
+                "</li>\n",
+                "</ol>\n",
+                "<p class=\"attribution\">—Author</p>\n",
+                "</blockquote>\n",
+                "<p>Code:</p>\n",
+                "<pre><code>if a &lt; b:\n    pass</code></pre>\n",
+                "<hr class=\"docutils\" />\n",
+                "<aside class=\"footnote\" id=\"note\">\n",
+                "<span class=\"label\">[1]</span>\n",
+                "<p>A footnote.</p>\n",
+                "</aside>\n",
+                "<aside class=\"footnote\" id=\"id1\">\n",
+                "<span class=\"label\">[*]</span>\n",
+                "<p>A symbol.</p>\n",
+                "</aside>\n",
+                "<aside class=\"footnote\" id=\"id2\">\n",
+                "<span class=\"label\">[†]</span>\n",
+                "<p>Another.</p>\n",
+                "</aside>\n",
+                "<!-- A comment - - with dashes. -->\n",
+            )
+        );
+    }
+
+    #[test]
+    fn comments() {
+        let html = |comment: &str| {
+            let document = Document(vec![SectionChildren::Body(BodyBlock::Comment(Comment(
+                comment.to_owned(),
+            )))]);
+            let mut html = Vec::new();
+            HtmlWriter::default()
+                .write_document(&document, &mut html)
+                .unwrap();
+            String::from_utf8(html).unwrap()
+        };
+
+        assert_eq!(html("Ends with -"), "<!-- Ends with - -->\n");
+        assert_eq!(html("---"), "<!-- - - - -->\n");
+        assert_eq!(html("a--!>b"), "<!-- a- -!>b -->\n");
+        assert_eq!(html("->x<!-"), "<!-- ->x<!- -->\n");
+        for comment in &["Ends with -", "---", "a--!>b", "->x<!-", "<!--x-->"] {
+            let html = html(comment);
+            let body = &html["<!--".len()..html.len() - "-->\n".len()];
+            assert!(!body.contains("--"), "{:?}", html);
+            assert!(
+                !body.starts_with('>') && !body.starts_with("->"),
+                "{:?}",
+                html
+            );
+            assert!(!body.ends_with('-') && !body.ends_with("<!-"), "{:?}", html);
+        }
+    }
+
+    #[test]
+    fn inline_markup() {
+        let word = |word: &str| Inline::Word(word.to_owned());
+        let text = Text(vec![
+            Inline::Emphasis(Emphasis(Text(vec![word("em")]))),
+            Inline::Whitespace,
+            Inline::Strong(Strong(Text(vec![word("strong")]))),
+            Inline::Whitespace,
+            Inline::Literal(Literal("a<b".to_owned())),
+            Inline::Whitespace,
+            Inline::Interpreted(Interpreted {
+                role: None,
+                text: "Title".to_owned(),
+            }),
+            Inline::Whitespace,
+            Inline::HyperlinkReference(HyperlinkReference {
+                label: Text(vec![word("Rust")]),
+                target: Some("https://www.rust-lang.org/?a=1&b=2".to_owned()),
+            }),
+            Inline::Whitespace,
+            Inline::HyperlinkReference(HyperlinkReference {
+                label: Text(vec![word("Getting"), Inline::Whitespace, word("started")]),
+                target: None,
+            }),
+            Inline::Whitespace,
+            Inline::Unit(Unit::Em(2.5)),
+        ]);
+
+        assert_eq!(
+            HtmlRenderer::new(&HtmlOptions::default(), HashMap::new()).inline_html(&text),
+            concat!(
+                "<em>em</em> <strong>strong</strong> <code>a&lt;b</code> <cite>Title</cite> ",
+                "<a class=\"reference external\" href=\"https://www.rust-lang.org/?a=1&amp;b=2\">",
+                "Rust</a> ",
+                "<a class=\"reference internal\" href=\"#getting-started\">Getting started</a> ",
+                "2.5em",
+            )
+        );
+    }
+
+    #[test]
+    fn sections() {
+        let text = concat!(
+            "Guide\n", "=====\n", "\n", "Setup\n", "-----\n", "\n", "Text.\n", "\n", "Setup\n",
+            "-----\n",
+        );
+        let options = HtmlOptions {
+            standalone: true,
+            section_id_prefix: "s-".to_owned(),
+            ..HtmlOptions::default()
+        };
+
+        assert_eq!(
+            render(text, options),
+            concat!(
+                "<!DOCTYPE html>\n",
+                "<html>\n",
+                "<head>\n",
+                "<meta charset=\"utf-8\">\n",
+                "<title>Guide</title>\n",
+                "</head>\n",
+                "<body>\n",
+                "<main>\n",
+                "<section id=\"s-guide\">\n",
+                "<h1>Guide</h1>\n",
+                "<section id=\"s-setup\">\n",
+                "<h2>Setup</h2>\n",
+                "<p>Text.</p>\n",
+                "</section>\n",
+                "<section id=\"s-id1\">\n",
+                "<h2>Setup</h2>\n",
+                "</section>\n",
+                "</section>\n",
+                "</main>\n",
+                "</body>\n",
+                "</html>\n",
+            )
+        );
+    }
+
+    #[test]
+    fn internal_references() {
+        let word = |word: &str| Inline::Word(word.to_owned());
+        let reference = |name: &str| {
+            Inline::HyperlinkReference(HyperlinkReference {
+                label: Text(vec![word(name)]),
+                target: None,
+            })
+        };
+        let section = |title: &str, children| {
+            SectionChildren::Section(Section {
+                title: title.to_owned(),
+                children,
+            })
+        };
+        let paragraph =
+            |inlines| SectionChildren::Body(BodyBlock::Paragraph(Paragraph(Text(inlines))));
+        let document = Document(vec![
+            paragraph(vec![
+                reference("setup"),
+                Inline::Whitespace,
+                reference("Anchor"),
+                Inline::Whitespace,
+                reference("Missing"),
+            ]),
+            section("Setup", vec![]),
+            section(
+                "Setup",
+                vec![paragraph(vec![Inline::Target(InlineInternalTarget(Text(
+                    vec![word("anchor")],
+                )))])],
+            ),
+            section("Anchor", vec![]),
+        ]);
+        let options = HtmlOptions {
+            section_id_prefix: "s-".to_owned(),
+            ..HtmlOptions::default()
+        };
+        let mut html = Vec::new();
+        HtmlWriter::new(options)
+            .write_document(&document, &mut html)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(html).unwrap(),
+            concat!(
+                "<p><a class=\"reference internal\" href=\"#s-setup\">setup</a> ",
+                "<a class=\"reference internal\" href=\"#anchor\">Anchor</a> ",
+                "<a class=\"reference internal\" href=\"#missing\">Missing</a></p>\n",
+                "<section id=\"s-setup\">\n",
+                "<h1>Setup</h1>\n",
+                "</section>\n",
+                "<section id=\"s-id1\">\n",
+                "<h1>Setup</h1>\n",
+                "<p><span class=\"target\" id=\"anchor\">anchor</span></p>\n",
+                "</section>\n",
+                "<section id=\"s-id2\">\n",
+                "<h1>Anchor</h1>\n",
+                "</section>\n",
+            )
+        );
+    }
+
+    #[test]
+    fn table_spans() {
+        let cell = |text: &str, column_span, row_span| Cell {
+            column_span,
+            row_span,
+            content: Body(vec![BodyBlock::Paragraph(Paragraph(Text(vec![
+                Inline::Word(text.to_owned()),
+            ])))]),
+        };
+        let document = Document(vec![SectionChildren::Body(BodyBlock::Table(Table {
+            header: vec![Row(vec![cell("Wide", 2, 1)])],
+            body: vec![Row(vec![cell("Tall", 1, 2), cell("A", 1, 1)])],
+        }))]);
+
+        let mut output = Vec::new();
+        HtmlWriter::default()
+            .write_document(&document, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                "<table>\n",
+                "<thead>\n",
+                "<tr>\n",
+                "<th colspan=\"2\">Wide</th>\n",
+                "</tr>\n",
+                "</thead>\n",
+                "<tbody>\n",
+                "<tr>\n",
+                "<td rowspan=\"2\">Tall</td>\n",
+                "<td>A</td>\n",
+                "</tr>\n",
+                "</tbody>\n",
+                "</table>\n",
+            )
+        );
+    }
+}
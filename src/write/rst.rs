@@ -0,0 +1,1207 @@
+//! Rendering of a document as reStructuredText.
+//!
+//! Blocks are rendered as lines without indentation, which the enclosing block indents. Blocks are
+//! always separated by a blank line.
+
+use std::io::{self, Write};
+
+use crate::adornment;
+use crate::ast::*;
+
+/// The column at which paragraphs are wrapped by default.
+const DEFAULT_WIDTH: usize = 79;
+
+/// The indentation of block quotes and literal blocks.
+const INDENT: &str = "    ";
+
+/// Writes a document as reStructuredText.
+///
+/// The output parses to the same document, except that an empty comment is written to separate
+/// a block quote from a preceding block that would otherwise absorb it, and that inline markup is
+/// read back as plain text because the parser does not yet recognise it. Hyperlink targets are
+/// written as explicit markup, although the parser does not yet read them. Paragraphs are
+/// wrapped to fit within the configured width where possible, and sections are adorned with the
+/// [default style](../adornment/fn.default_for_depth.html) for their depth.
+///
+/// Some blocks have no such output, because the parser does not read backslash escapes: a
+/// paragraph that would be read as a list or a comment, a paragraph ending in `::` that is not
+/// followed by a literal block, an empty paragraph, block quote or literal block, and a section
+/// with an empty title. These are reported as errors.
+///
+/// ```
+/// use rst::ast::Document;
+/// use rst::write::RstWriter;
+///
+/// let document: Document = "Title\n*****\n\nA   paragraph.\n".parse()?;
+///
+/// let mut output = Vec::new();
+/// RstWriter::new().write_document(&document, &mut output)?;
+/// assert_eq!(output, b"=======\n Title\n=======\n\nA paragraph.\n");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct RstWriter {
+    width: usize,
+}
+
+impl Default for RstWriter {
+    fn default() -> Self {
+        RstWriter {
+            width: DEFAULT_WIDTH,
+        }
+    }
+}
+
+impl RstWriter {
+    pub fn new() -> Self {
+        RstWriter::default()
+    }
+
+    /// Wrap paragraphs so that lines are at most `width` columns wide, including indentation.
+    ///
+    /// Words are never split, so a line holding a single long word may be wider.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Write the document.
+    ///
+    /// Fails if the output cannot be written, or if the document contains a block that cannot be
+    /// written so that it parses back the same, such as a table with cells spanning multiple rows
+    /// or columns.
+    pub fn write_document(&self, document: &Document, out: &mut impl Write) -> io::Result<()> {
+        for line in self.children(&document.0, 0)? {
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    fn children(&self, children: &[SectionChildren], depth: usize) -> io::Result<Vec<String>> {
+        let mut chunks = Vec::new();
+        let mut blocks = Vec::new();
+
+        for child in children {
+            if let SectionChildren::Body(block) = child {
+                blocks.push(block);
+                continue;
+            }
+
+            chunks.push(self.blocks(&blocks, 0)?);
+            blocks.clear();
+            match child {
+                SectionChildren::Transition => chunks.push(vec!["----".to_owned()]),
+                SectionChildren::Section(section) => chunks.push(self.section(section, depth)?),
+                SectionChildren::Body(_) => unreachable!("blocks are collected above"),
+            }
+        }
+        chunks.push(self.blocks(&blocks, 0)?);
+
+        Ok(join(chunks))
+    }
+
+    fn section(&self, section: &Section, depth: usize) -> io::Result<Vec<String>> {
+        if section.title.trim().is_empty() {
+            return Err(invalid(
+                "a section with an empty title cannot be written".to_owned(),
+            ));
+        }
+        let (character, overline) = adornment::default_for_depth(depth);
+        let width = section.title.chars().count();
+
+        let mut lines = Vec::new();
+        if overline {
+            let adornment: String = std::iter::repeat(character).take(width + 2).collect();
+            lines.push(adornment.clone());
+            lines.push(format!(" {}", section.title));
+            lines.push(adornment);
+        } else {
+            lines.push(section.title.clone());
+            lines.push(std::iter::repeat(character).take(width).collect());
+        }
+
+        let children = self.children(&section.children, depth + 1)?;
+        Ok(join(vec![lines, children]))
+    }
+
+    fn body(&self, body: &Body, indent: usize) -> io::Result<Vec<String>> {
+        let blocks: Vec<_> = body.0.iter().collect();
+        self.blocks(&blocks, indent)
+    }
+
+    /// Render a sequence of blocks to be indented by `indent` columns.
+    fn blocks(&self, blocks: &[&BodyBlock], indent: usize) -> io::Result<Vec<String>> {
+        let mut chunks = Vec::new();
+        let mut alternate = false;
+
+        for (index, block) in blocks.iter().enumerate() {
+            let previous = index.checked_sub(1).map(|index| blocks[index]);
+            let next = blocks.get(index + 1);
+
+            match block {
+                BodyBlock::Paragraph(Paragraph(text)) => {
+                    let literal = matches!(next, Some(BodyBlock::LiteralBlock(_)));
+                    chunks.push(self.paragraph(text, indent, literal)?);
+                }
+                BodyBlock::LiteralBlock(LiteralBlock(literal)) => {
+                    if literal.trim().is_empty() {
+                        return Err(invalid(
+                            "an empty literal block cannot be written".to_owned(),
+                        ));
+                    }
+                    if !matches!(previous, Some(BodyBlock::Paragraph(_))) {
+                        chunks.push(vec!["::".to_owned()]);
+                    }
+                    chunks.push(indented(INDENT, literal.lines().map(str::to_owned)));
+                }
+                BodyBlock::List(list) => {
+                    // Adjacent lists of the same kind alternate in style, so that the second is
+                    // not read as a continuation of the first.
+                    alternate = match previous {
+                        Some(BodyBlock::List(previous)) => {
+                            (previous.marker == ListMarker::Bullet)
+                                == (list.marker == ListMarker::Bullet)
+                                && !alternate
+                        }
+                        _ => false,
+                    };
+                    chunks.push(self.list(list, indent, alternate)?);
+                }
+                BodyBlock::BlockQuote(quote) => {
+                    // An empty comment ends a preceding block that would otherwise absorb the
+                    // indented quote.
+                    if previous.is_some_and(absorbs_indented) {
+                        chunks.push(vec!["..".to_owned()]);
+                    }
+                    chunks.push(self.block_quote(quote, indent)?);
+                }
+                block => chunks.push(self.block(block, indent)?),
+            }
+        }
+
+        Ok(join(chunks))
+    }
+
+    fn block(&self, block: &BodyBlock, indent: usize) -> io::Result<Vec<String>> {
+        let lines = match block {
+            BodyBlock::Paragraph(Paragraph(text)) => self.paragraph(text, indent, false)?,
+            BodyBlock::List(list) => self.list(list, indent, false)?,
+            BodyBlock::DefinitionList(DefinitionList(definitions)) => {
+                let mut chunks = Vec::new();
+                for definition in definitions {
+                    let mut term = inline_text(&definition.term);
+                    for classifier in &definition.classifiers {
+                        term.push_str(" : ");
+                        term.push_str(&inline_text(classifier));
+                    }
+                    let body = self.body(&definition.definition, indent + INDENT.len())?;
+                    let mut lines = vec![term];
+                    lines.extend(indented(INDENT, body));
+                    chunks.push(lines);
+                }
+                join(chunks)
+            }
+            BodyBlock::FieldList(fields) => self.field_list(fields, indent)?,
+            BodyBlock::OptionList(OptionList(items)) => {
+                let mut lines = Vec::new();
+                for item in items {
+                    let options: Vec<_> = item
+                        .options
+                        .iter()
+                        .map(|(option, argument)| match argument {
+                            Some(argument) => format!("{} {}", option, argument),
+                            None => option.clone(),
+                        })
+                        .collect();
+                    let marker = format!("{}  ", options.join(", "));
+                    let description = self.wrap(&item.description, indent + marker.len());
+                    lines.extend(prefixed(&marker, marker.len(), description));
+                }
+                lines
+            }
+            BodyBlock::LineBlock(LineBlock(lines)) => {
+                let mut output = Vec::new();
+                line_block(lines, 0, &mut output);
+                output
+            }
+            BodyBlock::DocTest(DocTest(doctest)) => doctest.lines().map(str::to_owned).collect(),
+            BodyBlock::Table(table) => table_lines(table)?,
+            BodyBlock::Footnote(footnote) => {
+                let label = match &footnote.identifier {
+                    FootnoteIdentifier::AutoNumbered => "#".to_owned(),
+                    FootnoteIdentifier::AutoSymbol => "*".to_owned(),
+                    FootnoteIdentifier::Numbered(number) => number.to_string(),
+                    FootnoteIdentifier::Labelled(label) => format!("#{}", label),
+                };
+                let body = self.body(&footnote.body, indent + 3)?;
+                prefixed(&format!(".. [{}] ", label), 3, body)
+            }
+            BodyBlock::Citation(citation) => {
+                let body = self.body(&citation.body, indent + 3)?;
+                prefixed(&format!(".. [{}] ", citation.name), 3, body)
+            }
+            BodyBlock::Target(target) => vec![target_line(target)],
+            BodyBlock::Directive(directive) => {
+                prefixed(".. ", 3, self.directive(directive, indent + 3)?)
+            }
+            BodyBlock::Substitution(substitution) => {
+                let directive = self.directive(&substitution.directive, indent + 3)?;
+                prefixed(&format!(".. |{}| ", substitution.text), 3, directive)
+            }
+            BodyBlock::Comment(Comment(comment)) => {
+                let mut lines = comment.lines().map(str::to_owned);
+                match lines.next() {
+                    Some(first) if !first.is_empty() => {
+                        prefixed(".. ", 3, std::iter::once(first).chain(lines).collect())
+                    }
+                    _ => prefixed(
+                        "..",
+                        3,
+                        std::iter::once(String::new()).chain(lines).collect(),
+                    ),
+                }
+            }
+            BodyBlock::LiteralBlock(_) | BodyBlock::BlockQuote(_) => {
+                let blocks = [block];
+                self.blocks(&blocks, indent)?
+            }
+        };
+
+        Ok(lines)
+    }
+
+    /// Render a paragraph, introducing a literal block if `literal` is set.
+    fn paragraph(&self, text: &Text, indent: usize, literal: bool) -> io::Result<Vec<String>> {
+        let mut units = units(text);
+        let first = match units.first() {
+            Some(first) if is_bullet(first) || first == ".." => {
+                return Err(invalid(format!(
+                    "a paragraph starting with {:?} cannot be written",
+                    first
+                )))
+            }
+            Some(first) => first.clone(),
+            None => return Err(invalid("an empty paragraph cannot be written".to_owned())),
+        };
+
+        // The parser removes a colon from a paragraph ending in "::", and reads any indented or
+        // punctuated lines following it as a literal block.
+        match units.last_mut() {
+            Some(last) if literal && last.ends_with(':') => last.push(':'),
+            _ if literal => units.push("::".to_owned()),
+            Some(last) if last.ends_with("::") => {
+                return Err(invalid(format!(
+                    "a paragraph ending in {:?} cannot be written without a literal block",
+                    last
+                )))
+            }
+            _ => {}
+        }
+
+        let width = self.width.saturating_sub(indent);
+        if !is_enumerator(&first) {
+            return Ok(wrap(&units, width));
+        }
+
+        // A paragraph starting with an enumerator is read as a list unless the line after the
+        // first is unindented and does not itself start with an enumerator.
+        let split = units
+            .iter()
+            .position(|unit| !is_enumerator(unit))
+            .ok_or_else(|| {
+                invalid(format!(
+                    "a paragraph of enumerators {:?} cannot be written",
+                    units.join(" ")
+                ))
+            })?;
+        let mut lines = vec![units[..split].join(" ")];
+        lines.extend(wrap(&units[split..], width));
+        Ok(lines)
+    }
+
+    fn wrap(&self, text: &Text, indent: usize) -> Vec<String> {
+        wrap(&units(text), self.width.saturating_sub(indent))
+    }
+
+    /// Render a list, with `*` bullets or `)` enumerators rather than the usual style if
+    /// `alternate` is set.
+    fn list(&self, list: &List, indent: usize, alternate: bool) -> io::Result<Vec<String>> {
+        let mut chunks = Vec::new();
+        for (index, element) in list.elements.iter().enumerate() {
+            let marker = match (&list.marker, alternate) {
+                (ListMarker::Bullet, false) => "- ".to_owned(),
+                (ListMarker::Bullet, true) => "* ".to_owned(),
+                (marker, false) => format!("{}. ", enumerator(marker, index)?),
+                (marker, true) => format!("{}) ", enumerator(marker, index)?),
+            };
+            let body = self.body(element, indent + marker.len())?;
+            chunks.push(prefixed(&marker, marker.len(), body));
+        }
+        Ok(join(chunks))
+    }
+
+    fn block_quote(&self, quote: &BlockQuote, indent: usize) -> io::Result<Vec<String>> {
+        if quote.quote.0.is_empty() {
+            return Err(invalid("an empty block quote cannot be written".to_owned()));
+        }
+        let indent = indent + INDENT.len();
+        let mut chunks = vec![self.body(&quote.quote, indent)?];
+        if let Some(attribution) = &quote.attribution {
+            chunks.push(prefixed("-- ", 3, self.wrap(attribution, indent + 3)));
+        }
+        Ok(indented(INDENT, join(chunks)))
+    }
+
+    fn field_list(&self, FieldList(fields): &FieldList, indent: usize) -> io::Result<Vec<String>> {
+        let mut lines = Vec::new();
+        for field in fields {
+            let marker = format!(":{}: ", inline_text(&field.marker));
+            let body = self.body(&field.body, indent + 3)?;
+            lines.extend(prefixed(&marker, 3, body));
+        }
+        Ok(lines)
+    }
+
+    /// Render a directive without the leading explicit markup.
+    fn directive(&self, directive: &Directive, indent: usize) -> io::Result<Vec<String>> {
+        let mut chunks = vec![vec![format!("{}::", directive.marker)]];
+        if !directive.fields.0.is_empty() {
+            chunks[0].extend(self.field_list(&directive.fields, indent)?);
+        }
+        match &directive.content {
+            DirectiveContent::Literal(literal) => {
+                chunks.push(literal.lines().map(str::to_owned).collect());
+            }
+            DirectiveContent::Parsed(body) => chunks.push(self.body(body, indent)?),
+        }
+
+        Ok(join(chunks))
+    }
+}
+
+/// The block ends with indented lines that would continue into an indented block following it.
+fn absorbs_indented(block: &BodyBlock) -> bool {
+    !matches!(
+        block,
+        BodyBlock::Comment(Comment(comment)) if comment.is_empty()
+    ) && !matches!(
+        block,
+        BodyBlock::Paragraph(_)
+            | BodyBlock::LineBlock(_)
+            | BodyBlock::DocTest(_)
+            | BodyBlock::Table(_)
+    )
+}
+
+/// Join chunks of lines with blank lines, skipping empty chunks.
+fn join(chunks: Vec<Vec<String>>) -> Vec<String> {
+    let mut lines = Vec::new();
+    for chunk in chunks.into_iter().filter(|chunk| !chunk.is_empty()) {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(chunk);
+    }
+    lines
+}
+
+/// Indent every line that is not blank.
+fn indented(indent: &str, lines: impl IntoIterator<Item = String>) -> Vec<String> {
+    lines
+        .into_iter()
+        .map(|line| match line.is_empty() {
+            true => line,
+            false => format!("{}{}", indent, line),
+        })
+        .collect()
+}
+
+/// Place a marker before the first line and indent the remaining lines by `indent` columns.
+fn prefixed(marker: &str, indent: usize, lines: Vec<String>) -> Vec<String> {
+    let mut lines = lines.into_iter();
+    let first = match lines.next() {
+        Some(first) if !first.is_empty() => format!("{}{}", marker, first),
+        _ => marker.trim_end().to_owned(),
+    };
+    let rest = indented(&" ".repeat(indent), lines);
+    std::iter::once(first).chain(rest).collect()
+}
+
+/// Split inline text into the units between which lines may be broken.
+fn units(text: &Text) -> Vec<String> {
+    let mut units = vec![String::new()];
+    for inline in &text.0 {
+        let unit = units.last_mut().expect("there is always a unit");
+        match inline {
+            Inline::Whitespace if unit.is_empty() => {}
+            Inline::Whitespace => units.push(String::new()),
+            inline => push_inline(unit, inline),
+        }
+    }
+
+    if units.last().is_some_and(String::is_empty) {
+        units.pop();
+    }
+    units
+}
+
+/// Inline text on a single line.
+fn inline_text(text: &Text) -> String {
+    units(text).join(" ")
+}
+
+fn push_inline(output: &mut String, inline: &Inline) {
+    match inline {
+        Inline::Emphasis(Emphasis(text)) => {
+            output.push('*');
+            output.push_str(&inline_text(text));
+            output.push('*');
+        }
+        Inline::Strong(Strong(text)) => {
+            output.push_str("**");
+            output.push_str(&inline_text(text));
+            output.push_str("**");
+        }
+        Inline::Interpreted(interpreted) => {
+            if let Some(role) = &interpreted.role {
+                output.push_str(&format!(":{}:", role));
+            }
+            output.push_str(&format!("`{}`", interpreted.text));
+        }
+        Inline::Literal(Literal(literal)) => output.push_str(&format!("``{}``", literal)),
+        Inline::HyperlinkReference(reference) => {
+            let label = inline_text(&reference.label);
+            match &reference.target {
+                Some(target) => output.push_str(&format!("`{} <{}>`_", label, target)),
+                None if label.chars().all(char::is_alphanumeric) => {
+                    output.push_str(&format!("{}_", label))
+                }
+                None => output.push_str(&format!("`{}`_", label)),
+            }
+        }
+        Inline::Target(InlineInternalTarget(text)) => {
+            output.push_str(&format!("_`{}`", inline_text(text)))
+        }
+        Inline::StandaloneHyperlink(StandaloneHyperlink(url)) => output.push_str(url.as_str()),
+        Inline::Unit(unit) => output.push_str(&unit.to_string()),
+        Inline::Word(word) => output.push_str(word),
+        Inline::Character(c) => output.push(*c),
+        Inline::Whitespace => output.push(' '),
+    }
+}
+
+/// Fill lines with units, breaking before any unit that would take a line beyond `width`.
+fn wrap(units: &[String], width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut length = 0;
+
+    for unit in units {
+        let unit_length = unit.chars().count();
+        if length > 0 && length + 1 + unit_length > width {
+            lines.push(std::mem::take(&mut line));
+            length = 0;
+        }
+        if length > 0 {
+            line.push(' ');
+            length += 1;
+        }
+        line.push_str(unit);
+        length += unit_length;
+    }
+
+    if length > 0 {
+        lines.push(line);
+    }
+    lines
+}
+
+/// The unit would be read as a bullet at the start of a paragraph.
+fn is_bullet(unit: &str) -> bool {
+    matches!(unit, "*" | "+" | "-" | "•" | "‣" | "⁃")
+}
+
+/// The unit would be read as an enumerator at the start of a paragraph.
+fn is_enumerator(unit: &str) -> bool {
+    let inner = match unit.strip_prefix('(') {
+        Some(inner) => inner.strip_suffix(')'),
+        None => unit.strip_suffix('.').or_else(|| unit.strip_suffix(')')),
+    };
+
+    match inner {
+        Some("#") => true,
+        Some(inner) if !inner.is_empty() => {
+            inner.chars().all(|c| c.is_ascii_digit())
+                || (inner.len() == 1 && inner.chars().all(|c| c.is_ascii_alphabetic()))
+                || inner.chars().all(|c| "ivxlcdm".contains(c))
+                || inner.chars().all(|c| "IVXLCDM".contains(c))
+        }
+        _ => false,
+    }
+}
+
+/// The enumerator of an item of an enumerated list.
+fn enumerator(marker: &ListMarker, index: usize) -> io::Result<String> {
+    let (latin, roman, uppercase) = match *marker {
+        ListMarker::Bullet => unreachable!("bullet lists have no enumerator"),
+        ListMarker::AutoNumbered => return Ok("#".to_owned()),
+        ListMarker::AutoSymbol => {
+            return Err(invalid("auto-symbol lists cannot be written".to_owned()))
+        }
+        ListMarker::Arabic(_) => (false, false, false),
+        ListMarker::LatinUppercase(_) => (true, false, true),
+        ListMarker::LatinLowercase(_) => (true, false, false),
+        ListMarker::RomanUppercase(_) => (false, true, true),
+        ListMarker::RomanLowercase(_) => (false, true, false),
+    };
+
+    let ordinal = marker
+        .resolved_number(index)
+        .ok_or_else(|| invalid(format!("the number of item {} overflows", index + 1)))?;
+    if !latin && !roman {
+        return Ok(ordinal.to_string());
+    }
+    let enumerator = if latin {
+        match ordinal {
+            1..=26 => char::from(b'a' + (ordinal - 1) as u8).to_string(),
+            _ => return Err(invalid(format!("no latin enumerator for {}", ordinal))),
+        }
+    } else if roman && (1..4000).contains(&ordinal) {
+        roman_numeral(ordinal)
+    } else {
+        return Err(invalid(format!("no roman enumerator for {}", ordinal)));
+    };
+
+    Ok(match uppercase {
+        true => enumerator.to_uppercase(),
+        false => enumerator,
+    })
+}
+
+fn roman_numeral(mut value: u64) -> String {
+    const NUMERALS: &[(u64, &str)] = &[
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+
+    let mut numeral = String::new();
+    for &(step, letters) in NUMERALS {
+        while value >= step {
+            numeral.push_str(letters);
+            value -= step;
+        }
+    }
+    numeral
+}
+
+/// Render a hyperlink target.
+fn target_line(target: &Target) -> String {
+    let name = match &target.name {
+        None => "__".to_owned(),
+        Some(name) if name.contains(':') => format!("_`{}`", name),
+        Some(name) => format!("_{}", name),
+    };
+    let content = match &target.content {
+        HyperlinkContent::Empty => return format!(".. {}:", name),
+        HyperlinkContent::URI(url) => url.to_string(),
+        HyperlinkContent::Email(address) => address.to_string(),
+        HyperlinkContent::Reference(reference) => {
+            if reference.chars().all(char::is_alphanumeric) {
+                format!("{}_", reference)
+            } else {
+                format!("`{}`_", reference)
+            }
+        }
+    };
+    format!(".. {}: {}", name, content)
+}
+
+fn line_block(lines: &[Line], depth: usize, output: &mut Vec<String>) {
+    for line in lines {
+        let text = inline_text(&line.content);
+        let indent = " ".repeat(INDENT.len() * depth);
+        output.push(format!("| {}{}", indent, text).trim_end().to_owned());
+        line_block(&line.children, depth + 1, output);
+    }
+}
+
+/// Render a grid table.
+fn table_lines(table: &Table) -> io::Result<Vec<String>> {
+    let rows: Vec<Vec<String>> = table
+        .header
+        .iter()
+        .chain(&table.body)
+        .map(|Row(cells)| {
+            let cells: Vec<_> = cells.iter().filter(|cell| !cell.is_covered()).collect();
+            if cells.iter().any(|c| c.column_span > 1 || c.row_span > 1) {
+                return Err(invalid("spanning table cells cannot be written".to_owned()));
+            }
+            cells
+                .iter()
+                .map(|cell| match &cell.content.0[..] {
+                    [] => Ok(String::new()),
+                    [BodyBlock::Paragraph(Paragraph(text))] => Ok(inline_text(text)),
+                    _ => Err(invalid(
+                        "table cells holding more than a paragraph cannot be written".to_owned(),
+                    )),
+                })
+                .collect()
+        })
+        .collect::<io::Result<_>>()?;
+
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let border = |fill: char| {
+        let mut line = String::from("+");
+        for width in &widths {
+            line.extend(std::iter::repeat(fill).take(width + 2));
+            line.push('+');
+        }
+        line
+    };
+
+    let mut lines = vec![border('-')];
+    for (index, row) in rows.iter().enumerate() {
+        let mut line = String::from("|");
+        for (column, width) in widths.iter().enumerate() {
+            let cell = row.get(column).map(String::as_str).unwrap_or("");
+            let padding = width - cell.chars().count();
+            line.push_str(&format!(" {}{} |", cell, " ".repeat(padding)));
+        }
+        lines.push(line);
+        let header = index + 1 == table.header.len();
+        lines.push(border(if header { '=' } else { '-' }));
+    }
+    Ok(lines)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::TextSource;
+    use crate::{Parser, TokenStream};
+
+    fn parse(text: &str) -> Document {
+        let mut source = TextSource::from_str("write", text);
+        Parser::new(TokenStream::try_new(&mut source).unwrap())
+            .parse()
+            .unwrap()
+    }
+
+    fn write(document: &Document, writer: &RstWriter) -> String {
+        let mut output = Vec::new();
+        writer.write_document(document, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    /// Write the parsed text, checking that the output parses to the same tree.
+    fn round_trip(text: &str) -> String {
+        let document = parse(text);
+        let output = write(&document, &RstWriter::new());
+        let reparsed = parse(&output);
+        assert_eq!(
+            reparsed.tree_string(),
+            document.tree_string(),
+            "from:\n{}",
+            output
+        );
+        assert_eq!(write(&reparsed, &RstWriter::new()), output);
+        output
+    }
+
+    #[test]
+    fn blocks() {
+        let text = concat!(
+            "Paragraph one\n",
+            "continues.\n",
+            "\n",
+            "* Bullet one.\n",
+            "* Bullet two\n",
+            "\n",
+            "  with a second paragraph.\n",
+            "\n",
+            "  C) Nested.\n",
+            "  D) Enumerated.\n",
+            "\n",
+            "iv. Roman.\n",
+            "v. Numerals.\n",
+            "\n",
+            "Between.\n",
+            "\n",
+            "#. Auto.\n",
+            "#. Numbered.\n",
+            "\n",
+            "A paragraph.\n",
+            "\n",
+            "    Quoted after a paragraph.\n",
+            "\n",
+            "    --- Attributed\n",
+            "\n",
+            "Code::\n",
+            "\n",
+            "  for a in b:\n",
+            "\n",
+            "      print a\n",
+            "\n",
+            "::\n",
+            "\n",
+            "  Bare literal.\n",
+            "\n",
+            ".. [#note] A footnote.\n",
+            "\n",
+            "   - With a list.\n",
+            ".. [CIT] Citation.\n",
+            ".. A comment\n",
+            "   on two lines.\n",
+        );
+
+        assert_eq!(
+            round_trip(text),
+            concat!(
+                "Paragraph one continues.\n",
+                "\n",
+                "- Bullet one.\n",
+                "\n",
+                "- Bullet two\n",
+                "\n",
+                "  with a second paragraph.\n",
+                "\n",
+                "  C. Nested.\n",
+                "\n",
+                "  D. Enumerated.\n",
+                "\n",
+                "iv. Roman.\n",
+                "\n",
+                "v. Numerals.\n",
+                "\n",
+                "Between.\n",
+                "\n",
+                "#. Auto.\n",
+                "\n",
+                "#. Numbered.\n",
+                "\n",
+                "A paragraph.\n",
+                "\n",
+                "    Quoted after a paragraph.\n",
+                "\n",
+                "    -- Attributed\n",
+                "\n",
+                "Code::\n",
+                "\n",
+                "    for a in b:\n",
+                "\n",
+                "        print a\n",
+                "\n",
+                "::\n",
+                "\n",
+                "    Bare literal.\n",
+                "\n",
+                ".. [#note] A footnote.\n",
+                "\n",
+                "   - With a list.\n",
+                "\n",
+                ".. [CIT] Citation.\n",
+                "\n",
+                ".. A comment\n",
+                "   on two lines.\n",
+            )
+        );
+    }
+
+    #[test]
+    fn sections() {
+        let text = concat!(
+            "Title\n",
+            "#####\n",
+            "\n",
+            "Chapter\n",
+            "+++++++\n",
+            "\n",
+            "Section\n",
+            "^^^^^^^\n",
+            "\n",
+            "Text.\n",
+            "\n",
+            "------------\n",
+            "\n",
+            "More text.\n",
+            "\n",
+            "Appendix\n",
+            "########\n",
+        );
+
+        assert_eq!(
+            round_trip(text),
+            concat!(
+                "=======\n",
+                " Title\n",
+                "=======\n",
+                "\n",
+                "---------\n",
+                " Chapter\n",
+                "---------\n",
+                "\n",
+                "Section\n",
+                "=======\n",
+                "\n",
+                "Text.\n",
+                "\n",
+                "----\n",
+                "\n",
+                "More text.\n",
+                "\n",
+                "==========\n",
+                " Appendix\n",
+                "==========\n",
+            )
+        );
+    }
+
+    #[test]
+    fn wrapping() {
+        let words = "The quick brown fox jumps over the lazy dog and keeps running";
+        let document = parse(&format!("{} {}\n\n- {}\n", words, words, words));
+        let output = write(&document, &RstWriter::new().with_width(40));
+        assert_eq!(
+            output,
+            concat!(
+                "The quick brown fox jumps over the lazy\n",
+                "dog and keeps running The quick brown\n",
+                "fox jumps over the lazy dog and keeps\n",
+                "running\n",
+                "\n",
+                "- The quick brown fox jumps over the\n",
+                "  lazy dog and keeps running\n",
+            )
+        );
+        assert_eq!(parse(&output).tree_string(), document.tree_string());
+
+        let url = format!("https://example.com/{}", "a".repeat(100));
+        let document = parse(&format!("See {} for details.\n", url));
+        assert_eq!(
+            write(&document, &RstWriter::new()),
+            format!("See\n{}\nfor details.\n", url)
+        );
+    }
+
+    #[test]
+    fn ambiguous_paragraphs() {
+        let mut inlines = Text::from_plain("back").0;
+        inlines.push(Inline::Character('\\'));
+        inlines.push(Inline::Word("slash".to_owned()));
+        let paragraph = |text| SectionChildren::Body(BodyBlock::Paragraph(Paragraph(text)));
+        let document = Document(vec![
+            paragraph(Text(inlines)),
+            paragraph(Text::from_plain("2. not a list")),
+            paragraph(Text::from_plain("1. 2. x")),
+            paragraph(Text::from_plain("2. ok")),
+            SectionChildren::Body(BodyBlock::LiteralBlock(LiteralBlock(
+                "C:\\ literal".to_owned(),
+            ))),
+            paragraph(Text::from_plain("Ends in::")),
+            SectionChildren::Body(BodyBlock::LiteralBlock(LiteralBlock("literal".to_owned()))),
+            SectionChildren::Body(BodyBlock::Comment(Comment(String::new()))),
+            SectionChildren::Body(BodyBlock::BlockQuote(BlockQuote {
+                quote: Body(vec![BodyBlock::Paragraph(Paragraph(Text::from_plain(
+                    "Quoted.",
+                )))]),
+                attribution: None,
+            })),
+        ]);
+
+        let output = write(&document, &RstWriter::new().with_width(12));
+        assert_eq!(
+            output,
+            concat!(
+                "back\\slash\n",
+                "\n",
+                "2.\n",
+                "not a list\n",
+                "\n",
+                "1. 2.\n",
+                "x\n",
+                "\n",
+                "2.\n",
+                "ok ::\n",
+                "\n",
+                "    C:\\ literal\n",
+                "\n",
+                "Ends in:::\n",
+                "\n",
+                "    literal\n",
+                "\n",
+                "..\n",
+                "\n",
+                "    Quoted.\n",
+            )
+        );
+        assert_eq!(parse(&output).tree_string(), document.tree_string());
+
+        let document_error = |children| {
+            RstWriter::new()
+                .write_document(&Document(children), &mut Vec::new())
+                .unwrap_err()
+                .to_string()
+        };
+        let error = |block| document_error(vec![SectionChildren::Body(block)]);
+        let paragraph = |words| BodyBlock::Paragraph(Paragraph(Text::from_plain(words)));
+        assert_eq!(
+            error(paragraph("- not a list")),
+            "a paragraph starting with \"-\" cannot be written"
+        );
+        assert_eq!(
+            error(paragraph(".. not a comment")),
+            "a paragraph starting with \"..\" cannot be written"
+        );
+        assert_eq!(
+            error(paragraph("Ends in ::")),
+            "a paragraph ending in \"::\" cannot be written without a literal block"
+        );
+        assert_eq!(
+            error(paragraph("3. iv)")),
+            "a paragraph of enumerators \"3. iv)\" cannot be written"
+        );
+        assert_eq!(
+            error(BodyBlock::Paragraph(Paragraph(Text(vec![])))),
+            "an empty paragraph cannot be written"
+        );
+        assert_eq!(
+            error(BodyBlock::BlockQuote(BlockQuote {
+                quote: Body(vec![]),
+                attribution: Some(Text::from_plain("Nobody")),
+            })),
+            "an empty block quote cannot be written"
+        );
+
+        let literal = |literal: &str| BodyBlock::LiteralBlock(LiteralBlock(literal.to_owned()));
+        assert_eq!(
+            document_error(vec![
+                SectionChildren::Body(paragraph("Code")),
+                SectionChildren::Body(literal("")),
+            ]),
+            "an empty literal block cannot be written"
+        );
+        assert_eq!(
+            error(literal(" \n")),
+            "an empty literal block cannot be written"
+        );
+        assert_eq!(
+            document_error(vec![SectionChildren::Section(Section {
+                title: String::new(),
+                children: vec![],
+            })]),
+            "a section with an empty title cannot be written"
+        );
+    }
+
+    #[test]
+    fn enumerator_overflow() {
+        let error = |marker| {
+            let list = List {
+                marker,
+                elements: vec![Body(vec![]), Body(vec![])],
+            };
+            let document = Document(vec![SectionChildren::Body(BodyBlock::List(list))]);
+            RstWriter::new()
+                .write_document(&document, &mut Vec::new())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error(ListMarker::Arabic(u64::MAX)),
+            "the number of item 2 overflows"
+        );
+        assert_eq!(
+            error(ListMarker::LatinLowercase(u64::MAX)),
+            "no latin enumerator for 18446744073709551615"
+        );
+        assert_eq!(
+            error(ListMarker::RomanUppercase(u64::MAX - 1)),
+            "no roman enumerator for 18446744073709551614"
+        );
+    }
+
+    #[test]
+    fn round_trip_every_block() {
+        round_trip(concat!(
+            "Title\n",
+            "=====\n",
+            "\n",
+            "A paragraph ending in::\n",
+            "\n",
+            "    Quoted after a colon.\n",
+            "\n",
+            "3. Item.\n",
+            "\n",
+            "- Adjacent bullets.\n",
+            "\n",
+            "* Adjacent bullets.\n",
+            "\n",
+            "+ Adjacent bullets.\n",
+            "\n",
+            "Literal::\n",
+            "\n",
+            "    literal\n",
+            "\n",
+            "::\n",
+            "\n",
+            "    expanded literal\n",
+            "\n",
+            "Quoted::\n",
+            "\n",
+            "> quoted literal\n",
+            "\n",
+            "----------\n",
+            "\n",
+            "- Bullet.\n",
+            "\n",
+            "    Quoted in the item.\n",
+            "\n",
+            "  Item paragraph.\n",
+            "- Second.\n",
+            "\n",
+            "..\n",
+            "\n",
+            "    Quoted after a list.\n",
+            "\n",
+            "    -- Attribution\n",
+            "\n",
+            "(ii) Roman.\n",
+            "(iii) Numerals.\n",
+            "\n",
+            "B) Latin.\n",
+            "\n",
+            "#. Auto.\n",
+            "#. Numbered.\n",
+            "\n",
+            "7. Arabic.\n",
+            "\n",
+            ".. [1] Numbered footnote.\n",
+            ".. [#] Auto footnote.\n",
+            ".. [#label] Labelled footnote.\n",
+            ".. [*] Symbol footnote.\n",
+            ".. [CIT] Citation.\n",
+            "\n",
+            ".. A comment\n",
+            "   over lines.\n",
+            "\n",
+            "..\n",
+            "   Comment on the next line.\n",
+            "\n",
+            "Second\n",
+            "======\n",
+            "\n",
+            "Back\\slash.\n",
+        ));
+    }
+
+    #[test]
+    fn other_blocks() {
+        let table = TableBuilder::new(2)
+            .header_row()
+            .cell("Name")
+            .cell("Value")
+            .row()
+            .cell("width")
+            .cell("80")
+            .build()
+            .unwrap();
+        let document = Document(vec![SectionChildren::Body(BodyBlock::Table(table))]);
+
+        assert_eq!(
+            write(&document, &RstWriter::new()),
+            concat!(
+                "+-------+-------+\n",
+                "| Name  | Value |\n",
+                "+=======+=======+\n",
+                "| width | 80    |\n",
+                "+-------+-------+\n",
+            )
+        );
+
+        let document = Document(vec![SectionChildren::Body(BodyBlock::Directive(
+            Directive {
+                marker: "note".to_owned(),
+                fields: FieldList(vec![Field {
+                    marker: Text::from_plain("class"),
+                    body: Body(vec![BodyBlock::Paragraph(Paragraph(Text::from_plain(
+                        "wide",
+                    )))]),
+                }]),
+                content: DirectiveContent::Parsed(Body(vec![BodyBlock::Paragraph(Paragraph(
+                    Text::from_plain("Body."),
+                ))])),
+            },
+        ))]);
+
+        assert_eq!(
+            write(&document, &RstWriter::new()),
+            ".. note::\n   :class: wide\n\n   Body.\n"
+        );
+
+        let target = |target| SectionChildren::Body(BodyBlock::Target(target));
+        let document = Document(vec![
+            target(Target::named(
+                "Python".to_owned(),
+                HyperlinkContent::URI("https://www.python.org/".parse().unwrap()),
+            )),
+            target(Target::named(
+                "a:b".to_owned(),
+                HyperlinkContent::Email("user@example.com".parse().unwrap()),
+            )),
+            target(Target::named(
+                "alias".to_owned(),
+                HyperlinkContent::Reference("Python".to_owned()),
+            )),
+            target(Target::named(
+                "other".to_owned(),
+                HyperlinkContent::Reference("a name".to_owned()),
+            )),
+            target(Target::named("here".to_owned(), HyperlinkContent::Empty)),
+            target(Target::anonymous(HyperlinkContent::URI(
+                "https://example.com/".parse().unwrap(),
+            ))),
+        ]);
+
+        assert_eq!(
+            write(&document, &RstWriter::new()),
+            concat!(
+                ".. _Python: https://www.python.org/\n",
+                "\n",
+                ".. _`a:b`: user@example.com\n",
+                "\n",
+                ".. _alias: Python_\n",
+                "\n",
+                ".. _other: `a name`_\n",
+                "\n",
+                ".. _here:\n",
+                "\n",
+                ".. __: https://example.com/\n",
+            )
+        );
+    }
+}